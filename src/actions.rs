@@ -0,0 +1,69 @@
+//! Actions run by key bindings and IPC commands
+
+use std::ffi::CString;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde::Serializer;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionResult {
+    KillClient,
+    Spawn(#[serde(serialize_with = "serialize_command")] Vec<CString>),
+    MoveToScreen(ScreenSwitching),
+    FocusOnScreen(ScreenSwitching),
+    UpdateMasterCapacity(i64),
+    UpdateMasterWidth(f64),
+    UpdateClientFactor(f64),
+    UpdateGaps(i64),
+    ToggleGaps,
+    ToggleScratchpad(String),
+    ToggleSticky,
+    Minimize,
+    RestoreLast,
+    /// Choose window with menu and jump to it, `true` pulls it to current workspace instead
+    PickWindow(bool),
+    /// Focus client with specified class, spawn `cmd` if there is none
+    RunOrRaise {
+        class: String,
+        #[serde(serialize_with = "serialize_command")]
+        cmd: Vec<CString>,
+    },
+    MoveToWorkspace(u64),
+    FocusOnWorkspace(u64),
+    CycleStack(i64),
+    MoveInStack(i64),
+    Zoom,
+    FocusDirection(Direction),
+    SwapDirection(Direction),
+    /// Same as [`ActionResult::Zoom`], kept for old configs
+    PopPushStack,
+    SetLayout(String),
+    CycleLayout,
+    ToggleFloat,
+    DumpInfo,
+    ReloadConfig,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenSwitching {
+    Next,
+    Previous,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Write command as list of strings instead of byte arrays
+fn serialize_command<S: Serializer>(cmd: &[CString], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(cmd.iter().map(|arg| arg.to_string_lossy()))
+}
@@ -0,0 +1,303 @@
+//! Config validation ran by `rtwm --check-config` without connecting to X server
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::config::NUMBER_OF_DESKTOPS;
+use crate::layouts::layout_index;
+use crate::loader::*;
+use crate::structs::*;
+use crate::wrapper::xlib::keysym_to_string;
+
+/// Validate config file and print found problems
+///
+/// 1. Parse file as TOML
+/// 2. Check all key names
+/// 3. Parse configuration
+/// 4. Check duplicate key bindings
+/// 5. Check placement & autostart rules
+/// 6. Check autostart, scratchpad & hook executables
+/// 7. Check layout names
+/// 8. Check scratchpads
+///
+/// Returns exit code: 0 if config is valid, 1 otherwise
+pub fn check_config(path: Option<PathBuf>) -> i32 {
+    let mut errors: Vec<String> = vec![];
+
+    // Only default config path may be missing, explicit one must exist
+    let explicit = path.is_some();
+    let path = match path.or_else(config_path) {
+        Some(p) => p,
+        None => {
+            eprintln!("error: can't find config path, set XDG_CONFIG_HOME or HOME");
+            return 1;
+        }
+    };
+
+    let config = if path.exists() {
+        // 1. Parse TOML
+        let text = match std::fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("error: failed to read {}: {}", path.display(), e);
+                return 1;
+            }
+        };
+        let value: toml::Value = match toml::from_str(&text) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("error: failed to parse {}: {}", path.display(), e);
+                return 1;
+            }
+        };
+
+        // 2. Keys
+        check_key_names(&value, &mut errors);
+        if !errors.is_empty() {
+            return report(&path, errors);
+        }
+
+        // 3. Configuration
+        match value.try_into::<Configuration>() {
+            Ok(c) => c,
+            Err(e) => {
+                errors.push(e.to_string());
+                return report(&path, errors);
+            }
+        }
+    } else if explicit {
+        eprintln!("error: {} does not exist", path.display());
+        return 1;
+    } else {
+        println!(
+            "{} does not exist, checking compiled-in config",
+            path.display()
+        );
+        Configuration::default()
+    };
+
+    // 4-8
+    check_duplicate_keys(&config, &mut errors);
+    check_rules(&config, &mut errors);
+    check_executables(&config, &mut errors);
+    check_layouts(&config, &mut errors);
+    check_scratchpads(&config, &mut errors);
+
+    report(&path, errors)
+}
+
+/// Print errors and return exit code
+fn report(path: &Path, errors: Vec<String>) -> i32 {
+    for e in &errors {
+        eprintln!("error: {}", e);
+    }
+    if errors.is_empty() {
+        println!("{}: OK", path.display());
+        0
+    } else {
+        eprintln!("{}: {} error(s)", path.display(), errors.len());
+        1
+    }
+}
+
+/// Collect every unknown keysym or modifier instead of stopping at first one
+fn check_key_names(value: &toml::Value, errors: &mut Vec<String>) {
+    if let Some(actions) = value.get("key_actions").and_then(|a| a.as_array()) {
+        for (index, action) in actions.iter().enumerate() {
+            if let Some(key) = action.get("key").and_then(|k| k.as_str()) {
+                if let Err(e) = parse_key(key) {
+                    errors.push(format!("key_actions[{}]: {}", index, e));
+                }
+            }
+        }
+    }
+    if let Some(keysyms) = value
+        .get("desktops")
+        .and_then(|d| d.get("keysyms"))
+        .and_then(|k| k.as_array())
+    {
+        for (index, keysym) in keysyms.iter().enumerate() {
+            if let Some(name) = keysym.as_str() {
+                if parse_keysym(name).is_none() {
+                    errors.push(format!(
+                        "desktops.keysyms[{}]: unknown keysym `{}`",
+                        index, name
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Human readable name of key combination
+fn key_name(action: &KeyAction) -> String {
+    let mut name = String::new();
+    for (mask, mask_name) in [
+        (x11::xlib::ControlMask, "Control"),
+        (x11::xlib::Mod1Mask, "Mod1"),
+        (x11::xlib::Mod4Mask, "Mod4"),
+        (x11::xlib::ShiftMask, "Shift"),
+        (x11::xlib::LockMask, "Lock"),
+        (x11::xlib::Mod2Mask, "Mod2"),
+        (x11::xlib::Mod3Mask, "Mod3"),
+        (x11::xlib::Mod5Mask, "Mod5"),
+    ] {
+        if action.modifier & mask != 0 {
+            name.push_str(mask_name);
+            name.push('+');
+        }
+    }
+    match keysym_to_string(action.keysym) {
+        Some(k) => name.push_str(&k),
+        None => name.push_str(&format!("{:#x}", action.keysym)),
+    }
+    name
+}
+
+/// Same keysym and modifier bound more than once
+fn check_duplicate_keys(config: &Configuration, errors: &mut Vec<String>) {
+    let actions = &config.key_actions;
+    for (i, first) in actions.iter().enumerate() {
+        if let Some(second) = actions[i + 1..]
+            .iter()
+            .find(|a| a.keysym == first.keysym && a.modifier == first.modifier)
+        {
+            // Report only first pair for every key
+            if actions[..i]
+                .iter()
+                .any(|a| a.keysym == first.keysym && a.modifier == first.modifier)
+            {
+                continue;
+            }
+            errors.push(format!(
+                "`{}` is bound to both {:?} and {:?}",
+                key_name(first),
+                first.result,
+                second.result
+            ));
+        }
+    }
+}
+
+/// Placement & autostart rules must reference existing workspaces,
+/// placement rules must match windows by something
+fn check_rules(config: &Configuration, errors: &mut Vec<String>) {
+    for (index, rule) in config.placements.iter().enumerate() {
+        if rule.instance.is_none() && rule.class.is_none() && rule.title.is_none() {
+            errors.push(format!(
+                "placements[{}]: rule needs instance, class or title, otherwise it matches every window",
+                index
+            ));
+        }
+        if let Some(w) = rule.rule_workspace {
+            if w >= NUMBER_OF_DESKTOPS {
+                errors.push(format!(
+                    "placements[{}]: workspace {} is out of range, there are only {} desktops",
+                    index, w, NUMBER_OF_DESKTOPS
+                ));
+            }
+        }
+    }
+    for (index, rule) in config.autostart.iter().enumerate() {
+        if let Some((_, w)) = rule.rule {
+            if w >= NUMBER_OF_DESKTOPS {
+                errors.push(format!(
+                    "autostart[{}]: workspace {} is out of range, there are only {} desktops",
+                    index, w, NUMBER_OF_DESKTOPS
+                ));
+            }
+        }
+    }
+    for (index, names) in config.desktops.names.iter().enumerate() {
+        if names.iter().any(|n| n.is_empty()) {
+            errors.push(format!("desktops.names[{}]: empty desktop name", index));
+        }
+    }
+}
+
+/// Autostart, scratchpad & hook commands must be runnable
+fn check_executables(config: &Configuration, errors: &mut Vec<String>) {
+    let commands = config
+        .autostart
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| (format!("autostart[{}]", index), &rule.cmd))
+        .chain(
+            config
+                .scratchpads
+                .iter()
+                .enumerate()
+                .map(|(index, rule)| (format!("scratchpads[{}]", index), &rule.cmd)),
+        )
+        .chain(
+            config
+                .hooks
+                .iter()
+                .enumerate()
+                .map(|(index, rule)| (format!("hooks[{}]", index), &rule.cmd)),
+        );
+    for (name, cmd) in commands {
+        let program = match cmd.first() {
+            Some(p) => p.to_string_lossy().to_string(),
+            None => {
+                errors.push(format!("{}: empty command", name));
+                continue;
+            }
+        };
+        if find_executable(&program).is_none() {
+            errors.push(format!("{}: `{}` is not found in PATH", name, program));
+        }
+    }
+}
+
+/// Resolve program the same way execvp does
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+    let is_executable = |p: &Path| {
+        p.metadata()
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+    if program.contains('/') {
+        let p = PathBuf::from(program);
+        return if is_executable(&p) { Some(p) } else { None };
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|p| is_executable(p))
+}
+
+/// Layouts set by key actions must exist
+fn check_layouts(config: &Configuration, errors: &mut Vec<String>) {
+    for action in &config.key_actions {
+        if let ActionResult::SetLayout(name) = &action.result {
+            if layout_index(name).is_none() {
+                errors.push(format!("`{}`: unknown layout `{}`", key_name(action), name));
+            }
+        }
+    }
+}
+
+/// Scratchpads must be matchable and toggled ones must exist
+fn check_scratchpads(config: &Configuration, errors: &mut Vec<String>) {
+    for (index, rule) in config.scratchpads.iter().enumerate() {
+        if rule.instance.is_none() && rule.class.is_none() {
+            errors.push(format!(
+                "scratchpads[{}]: `{}` needs instance or class to match its window",
+                index, rule.name
+            ));
+        }
+    }
+    for action in &config.key_actions {
+        if let ActionResult::ToggleScratchpad(name) = &action.result {
+            if !config.scratchpads.iter().any(|r| r.name == *name) {
+                errors.push(format!(
+                    "`{}`: unknown scratchpad `{}`",
+                    key_name(action),
+                    name
+                ));
+            }
+        }
+    }
+}
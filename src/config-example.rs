@@ -8,8 +8,11 @@ use crate::structs::AutostartRuleCMD;
 use crate::structs::Color;
 use crate::structs::Configuration;
 use crate::structs::DesktopsConfig;
+use crate::structs::Direction;
+use crate::structs::HookRule;
 use crate::structs::KeyAction;
 use crate::structs::PlacementRule;
+use crate::structs::ScratchpadRule;
 use crate::structs::ScreenSwitching;
 
 use x11::keysym::*;
@@ -77,13 +80,15 @@ pub fn config() -> Configuration {
             }),
             rule_screen,
             rule_workspace,
+            sticky: false,
         }
     }
 
     //-----------------------------------------------------------------------
     //                               Visuals
     //-----------------------------------------------------------------------
-    let gap_width = 4;
+    let inner_gap_width = 4;
+    let outer_gap_width = 4;
     let border_size = 2;
     let normal_border_color = Color {
         //#404080
@@ -107,12 +112,23 @@ pub fn config() -> Configuration {
         blue: 28,
     };
 
+    //-----------------------------------------------------------------------
+    //                               Behaviour
+    //-----------------------------------------------------------------------
+    // Move pointer to window focused with keyboard
+    let warp_pointer = false;
+    // Drop gaps and borders when only one tiled window is visible
+    let smart_gaps = true;
+    let smart_borders = true;
+
     //-----------------------------------------------------------------------
     //                          Shortcuts setup
     //-----------------------------------------------------------------------
     let terminal = CMD!("alacritty");
     let file_manager = CMD!("thunar");
     let app_launcher = CMD!("dmenu_run", "-p", "Open app:", "-b");
+    // Menu reading choices from stdin, used to pick windows
+    let window_picker = CMD!("dmenu", "-i", "-l", "20", "-p", "Window:");
 
     let mut key_actions = vec![
         KeyAction {
@@ -171,6 +187,11 @@ pub fn config() -> Configuration {
             keysym: XK_c,
             result: KillClient,
         },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_r,
+            result: ReloadConfig,
+        },
         KeyAction {
             modifier: ModKey,
             keysym: XK_w,
@@ -216,11 +237,109 @@ pub fn config() -> Configuration {
             keysym: XK_h,
             result: UpdateMasterWidth(-0.05),
         },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_l,
+            result: UpdateClientFactor(0.25),
+        },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_h,
+            result: UpdateClientFactor(-0.25),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_equal,
+            result: UpdateGaps(2),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_minus,
+            result: UpdateGaps(-2),
+        },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_g,
+            result: ToggleGaps,
+        },
         KeyAction {
             modifier: ModKey | ShiftMask,
             keysym: XK_space,
             result: ToggleFloat,
         },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_grave,
+            result: ToggleScratchpad("term".to_string()),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_y,
+            result: ToggleSticky,
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_n,
+            result: Minimize,
+        },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_n,
+            result: RestoreLast,
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_o,
+            result: PickWindow(false),
+        },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_o,
+            result: PickWindow(true),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_f,
+            result: RunOrRaise {
+                class: "firefox".to_string(),
+                cmd: CMD!("firefox"),
+            },
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_t,
+            result: SetLayout("tile".to_string()),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_m,
+            result: SetLayout("monocle".to_string()),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_g,
+            result: SetLayout("grid".to_string()),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_b,
+            result: SetLayout("bstack".to_string()),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_u,
+            result: SetLayout("centeredmaster".to_string()),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_s,
+            result: SetLayout("dwindle".to_string()),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_Tab,
+            result: CycleLayout,
+        },
         KeyAction {
             modifier: ModKey,
             keysym: XK_j,
@@ -231,8 +350,41 @@ pub fn config() -> Configuration {
             keysym: XK_k,
             result: CycleStack(1),
         },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_j,
+            result: MoveInStack(-1),
+        },
+        KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym: XK_k,
+            result: MoveInStack(1),
+        },
+        KeyAction {
+            modifier: ModKey,
+            keysym: XK_z,
+            result: Zoom,
+        },
     ];
 
+    for (keysym, direction) in [
+        (XK_Left, Direction::Left),
+        (XK_Right, Direction::Right),
+        (XK_Up, Direction::Up),
+        (XK_Down, Direction::Down),
+    ] {
+        key_actions.push(KeyAction {
+            modifier: ModKey,
+            keysym,
+            result: FocusDirection(direction),
+        });
+        key_actions.push(KeyAction {
+            modifier: ModKey | ShiftMask,
+            keysym,
+            result: SwapDirection(direction),
+        });
+    }
+
     //-----------------------------------------------------------------------
     //                          Desktops Setup
     //-----------------------------------------------------------------------
@@ -291,18 +443,49 @@ pub fn config() -> Configuration {
         mk_placement(None, Some("Arandr"), None, Some(0), Some(9)),
     ];
 
+    //-----------------------------------------------------------------------
+    //                          Scratchpads setup
+    //-----------------------------------------------------------------------
+    // Window is matched by instance and class, so program must set them
+    let scratchpads = vec![ScratchpadRule {
+        name: "term".to_string(),
+        cmd: CMD!("alacritty", "--class", "scratchpad"),
+        instance: Some("scratchpad".to_string()),
+        class: None,
+    }];
+
+    //-----------------------------------------------------------------------
+    //                             Hooks setup
+    //-----------------------------------------------------------------------
+    // Commands get event details in RTWM_WINDOW, RTWM_CLASS, RTWM_SCREEN
+    // and RTWM_WORKSPACE environment variables. ScreenAdded and ScreenRemoved
+    // are run only when screens change while WM is running, not on start
+    let hooks: Vec<HookRule> = vec![
+        // HookRule {
+        //     event: crate::structs::HookEvent::ClientUrgent,
+        //     cmd: CMD!("sh", "-c", "notify-send \"$RTWM_CLASS needs attention\""),
+        // },
+    ];
+
     //-----------------------------------------------------------------------
     //                      Create config & return
     //-----------------------------------------------------------------------
     return Configuration {
         key_actions,
-        gap_width,
+        inner_gap_width,
+        outer_gap_width,
         border_size,
         normal_border_color,
         active_border_color,
         urgent_border_color,
+        warp_pointer,
+        smart_gaps,
+        smart_borders,
+        window_picker,
         desktops,
         autostart,
         placements,
+        scratchpads,
+        hooks,
     };
 }
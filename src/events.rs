@@ -7,7 +7,6 @@ use x11::xlib::CWBorderWidth;
 use x11::xlib::CWHeight;
 use x11::xlib::CWWidth;
 use x11::xlib::CurrentTime;
-use x11::xlib::PropModeReplace;
 use x11::xlib::RevertToPointerRoot;
 use x11::xlib::XButtonEvent;
 use x11::xlib::XClientMessageEvent;
@@ -24,12 +23,12 @@ use x11::xlib::XUnmapEvent;
 use x11::xlib::XWindowChanges;
 use x11::xlib::CWX;
 use x11::xlib::CWY;
-use x11::xlib::XA_ATOM;
 
 use crate::helper::*;
 use crate::logic::*;
 use crate::manage::*;
 use crate::mouse::*;
+use crate::server::emit_focus;
 use crate::structs::*;
 use crate::utils::*;
 use crate::wrapper::xlib::*;
@@ -42,50 +41,105 @@ pub fn key_press(app: &mut Application, key_event: XKeyEvent) {
         if key_event.keycode == keysym_to_keycode(app.core.display, action.keysym)
             && key_event.state == action.modifier
         {
-            // Match action result and run related function
-            match &action.result {
-                ActionResult::KillClient => {
-                    kill_client(app);
-                }
-                ActionResult::Spawn(cmd) => {
-                    spawn(app, &cmd.clone(), None);
-                }
-                ActionResult::MoveToScreen(d) => {
-                    move_to_screen(app, *d);
-                }
-                ActionResult::FocusOnScreen(d) => {
-                    focus_on_screen(app, *d);
-                }
-                ActionResult::MoveToWorkspace(n) => {
-                    move_to_workspace(app, *n);
-                }
-                ActionResult::FocusOnWorkspace(n) => {
-                    focus_on_workspace(app, *n, true);
-                }
-                ActionResult::Quit => {
-                    app.core.running = false;
-                }
-                ActionResult::UpdateMasterCapacity(i) => {
-                    update_master_capacity(app, *i);
-                }
-                ActionResult::UpdateMasterWidth(w) => {
-                    update_master_width(app, *w);
-                }
-                ActionResult::DumpInfo => {
-                    log!("{:#?}", &app.runtime);
-                }
-                ActionResult::ToggleFloat => {
-                    toggle_float(app);
-                }
-                ActionResult::CycleStack(_i) => {}
-                ActionResult::PopPushStack => {
-                    move_to_workspace(app, app.runtime.current_workspace as u64);
-                }
+            run_action(app, &action.result);
+            // Key actions may have changed
+            if let ActionResult::ReloadConfig = action.result {
+                return;
             }
         }
     }
 }
 
+/// Match action result and run related function
+pub fn run_action(app: &mut Application, action: &ActionResult) {
+    match action {
+        ActionResult::KillClient => {
+            kill_client(app);
+        }
+        ActionResult::Spawn(cmd) => {
+            spawn(app, &cmd.clone(), None);
+        }
+        ActionResult::MoveToScreen(d) => {
+            move_to_screen(app, *d);
+        }
+        ActionResult::FocusOnScreen(d) => {
+            focus_on_screen(app, *d);
+        }
+        ActionResult::MoveToWorkspace(n) => {
+            move_to_workspace(app, *n);
+        }
+        ActionResult::FocusOnWorkspace(n) => {
+            focus_on_workspace(app, *n, true);
+        }
+        ActionResult::Quit => {
+            app.core.running = false;
+        }
+        ActionResult::ReloadConfig => {
+            reload_config(app);
+        }
+        ActionResult::UpdateMasterCapacity(i) => {
+            update_master_capacity(app, *i);
+        }
+        ActionResult::UpdateMasterWidth(w) => {
+            update_master_width(app, *w);
+        }
+        ActionResult::UpdateClientFactor(f) => {
+            update_client_factor(app, *f);
+        }
+        ActionResult::UpdateGaps(i) => {
+            update_gaps(app, *i);
+        }
+        ActionResult::ToggleGaps => {
+            toggle_gaps(app);
+        }
+        ActionResult::ToggleScratchpad(name) => {
+            toggle_scratchpad(app, name);
+        }
+        ActionResult::ToggleSticky => {
+            toggle_sticky(app);
+        }
+        ActionResult::Minimize => {
+            minimize(app);
+        }
+        ActionResult::RestoreLast => {
+            restore_last(app);
+        }
+        ActionResult::PickWindow(pull) => {
+            pick_window(app, *pull);
+        }
+        ActionResult::RunOrRaise { class, cmd } => {
+            run_or_raise(app, class, cmd);
+        }
+        ActionResult::DumpInfo => {
+            log!("{:#?}", &app.runtime);
+        }
+        ActionResult::ToggleFloat => {
+            toggle_float(app);
+        }
+        ActionResult::CycleStack(i) => {
+            cycle_stack(app, *i);
+        }
+        ActionResult::MoveInStack(i) => {
+            move_in_stack(app, *i);
+        }
+        ActionResult::Zoom | ActionResult::PopPushStack => {
+            zoom(app);
+        }
+        ActionResult::FocusDirection(d) => {
+            focus_direction(app, *d);
+        }
+        ActionResult::SwapDirection(d) => {
+            swap_direction(app, *d);
+        }
+        ActionResult::SetLayout(name) => {
+            set_layout(app, name);
+        }
+        ActionResult::CycleLayout => {
+            cycle_layout(app);
+        }
+    }
+}
+
 pub fn map_request(app: &mut Application, map_request: XMapRequestEvent) {
     let ew: u64 = map_request.window;
     log!("|- Map Request From Window: {ew}");
@@ -120,6 +174,7 @@ pub fn enter_notify(app: &mut Application, crossing_event: XCrossingEvent) {
                 app.core.root_win,
                 app.atoms.net_active_window,
             );
+            emit_focus(app, None);
         }
     }
 }
@@ -135,17 +190,7 @@ pub fn unmap_notify(app: &mut Application, unmap_event: XUnmapEvent) {
     log!("|- `{}` ({}) unmapped", get_client_name(app, ew), ew);
     if let Some(_) = find_window_indexes(app, ew) {
         if unmap_event.send_event == 1 {
-            let data: [i64; 2] = [0, 0];
-            change_property(
-                app.core.display,
-                ew,
-                app.atoms.wm_state,
-                app.atoms.wm_state,
-                32,
-                PropModeReplace,
-                &data as *const [i64; 2] as *mut u8,
-                2,
-            );
+            set_wm_state(app, ew, WITHDRAWN_STATE);
         } else {
             unmanage_window(app, ew);
         }
@@ -170,7 +215,11 @@ pub fn motion_notify(
 }
 
 pub fn property_notify(app: &mut Application, property_event: XPropertyEvent) {
-    if property_event.window != app.core.root_win {
+    // Only name changes are tracked
+    if property_event.window != app.core.root_win
+        && (property_event.atom == app.atoms.net_wm_name
+            || property_event.atom == app.atoms.wm_name)
+    {
         update_client_name(app, property_event.window);
     }
 }
@@ -215,48 +264,47 @@ pub fn client_message(app: &mut Application, client_event: XClientMessageEvent)
                 let sf = client_event.data.get_long(0) == 1
                     || client_event.data.get_long(0) == 2 && cc.fullscreen;
                 if sf && !cc.fullscreen {
-                    change_property(
-                        app.core.display,
-                        client_event.window,
-                        app.atoms.net_wm_state,
-                        XA_ATOM,
-                        32,
-                        PropModeReplace,
-                        &mut app.atoms.net_wm_fullscreen as *mut u64 as *mut u8,
-                        1,
-                    );
                     cc.ow = cc.w;
                     cc.oh = cc.h;
                     cc.w = client_screen.width as u32;
                     cc.h = client_screen.height as u32;
                     cc.fullscreen = true;
                 } else if !sf && cc.fullscreen {
-                    change_property(
-                        app.core.display,
-                        client_event.window,
-                        app.atoms.net_wm_state,
-                        XA_ATOM,
-                        32,
-                        PropModeReplace,
-                        std::ptr::null_mut::<u8>(),
-                        0,
-                    );
                     cc.w = cc.ow;
                     cc.h = cc.oh;
                     cc.fullscreen = false;
                 }
+                update_client_state(app, client_event.window);
                 arrange_current(app);
                 show_workspace(
                     app,
                     app.runtime.current_screen,
                     app.runtime.current_workspace,
                 );
+            } else if client_event.data.get_long(1) as u64 == app.atoms.net_wm_state_sticky
+                || client_event.data.get_long(2) as u64 == app.atoms.net_wm_state_sticky
+            {
+                let sticky = client_event.data.get_long(0) == 1
+                    || client_event.data.get_long(0) == 2 && !cc.sticky;
+                set_sticky(app, client_event.window, sticky);
+            } else if client_event.data.get_long(1) as u64 == app.atoms.net_wm_state_hidden
+                || client_event.data.get_long(2) as u64 == app.atoms.net_wm_state_hidden
+            {
+                let minimized = client_event.data.get_long(0) == 1
+                    || client_event.data.get_long(0) == 2 && !cc.minimized;
+                set_minimized(app, client_event.window, minimized);
             } else {
                 log!("      |- Unsupported `state`");
             }
+        } else if client_event.message_type == app.atoms.wm_change_state {
+            if client_event.data.get_long(0) == ICONIC_STATE {
+                set_minimized(app, client_event.window, true);
+            }
         } else if client_event.message_type == app.atoms.net_active_window {
             log!("=== GOT URGENCY TRIGGER");
-            if client_workspace_index != client_screen.current_workspace && !cc.urgent {
+            if cc.minimized {
+                set_minimized(app, client_event.window, false);
+            } else if client_workspace_index != client_screen.current_workspace && !cc.urgent {
                 log!("=== SET URGENCY FLAG");
                 let win = cc.window_id;
                 set_urgent(app, win, true);
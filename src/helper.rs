@@ -5,8 +5,13 @@ use std::mem::size_of;
 use std::ptr::null_mut;
 
 use crate::config;
+use crate::ipc::Event;
+use crate::layouts::*;
+use crate::server::emit_event;
+use crate::server::emit_focus;
 use crate::structs::*;
 use crate::utils::*;
+use crate::wrapper::sys::set_nonblocking;
 use crate::wrapper::xlib::*;
 
 use x11::xlib::Atom;
@@ -26,8 +31,15 @@ use x11::xlib::XA_ATOM;
 use x11::xlib::XA_WINDOW;
 use x11::xlib::{PropModeReplace, XA_CARDINAL};
 
-/// Set desktop for specified window
+/// `_NET_WM_DESKTOP` of windows shown on all desktops
+pub const ALL_DESKTOPS: u64 = 0xFFFFFFFF;
+
+/// Set desktop for specified window, sticky windows are on all desktops
 pub fn update_client_desktop(app: &mut Application, win: u64, desk: u64) {
+    let desk = match find_window_indexes(app, win) {
+        Some((s, w, c)) if app.runtime.screens[s].workspaces[w].clients[c].sticky => ALL_DESKTOPS,
+        _ => desk,
+    };
     change_property(
         app.core.display,
         win,
@@ -81,7 +93,8 @@ pub fn update_active_window(app: &mut Application) {
         );
     } else if ws.screens[ws.current_screen].workspaces[ws.current_workspace]
         .clients
-        .is_empty()
+        .iter()
+        .all(|c| c.minimized)
     {
         set_input_focus(
             app.core.display,
@@ -95,6 +108,8 @@ pub fn update_active_window(app: &mut Application) {
             app.atoms.net_active_window,
         );
     }
+    let focused = get_current_client_id(app);
+    emit_focus(app, focused);
 }
 
 /// Returns window, workspace and client indexies for client with specified id
@@ -112,6 +127,31 @@ pub fn find_window_indexes(app: &mut Application, win: u64) -> Option<(usize, us
     None
 }
 
+/// Returns instance and class from `WM_CLASS` of window
+pub fn get_client_class(app: &mut Application, win: u64) -> (Option<String>, Option<String>) {
+    let mut ch: x11::xlib::XClassHint = x11::xlib::XClassHint {
+        res_name: std::ptr::null_mut(),
+        res_class: std::ptr::null_mut(),
+    };
+    get_class_hint(app.core.display, win, &mut ch);
+
+    let instance = cstr_to_string(ch.res_name as *const i8);
+    let class = cstr_to_string(ch.res_class as *const i8);
+
+    (instance, class)
+}
+
+/// Place client in the middle of screen area not covered by bars
+pub fn center_client(client: &mut Client, screen: &Screen) {
+    let offsets = screen.bar_offsets;
+    let width = screen.width - (offsets.left + offsets.right) as i64;
+    let height = screen.height - (offsets.up + offsets.down) as i64;
+    client.x = (screen.x + offsets.left as i64 + (width - client.w as i64) / 2) as i32
+        - client.border as i32;
+    client.y = (screen.y + offsets.up as i64 + (height - client.h as i64) / 2) as i32
+        - client.border as i32;
+}
+
 // TODO: What is going on here
 pub fn get_atom_prop(app: &mut Application, win: u64, prop: Atom) -> Atom {
     let mut dummy_atom: u64 = 0;
@@ -144,6 +184,95 @@ pub fn get_atom_prop(app: &mut Application, win: u64, prop: Atom) -> Atom {
     atom
 }
 
+/// Returns every atom of list property, e.g. `_NET_WM_STATE`
+pub fn get_atom_list_prop(app: &mut Application, win: u64, prop: Atom) -> Vec<Atom> {
+    let mut dummy_atom: u64 = 0;
+    let mut dummy_int: i32 = 0;
+    let mut items: u64 = 0;
+    let mut dummy_long: u64 = 0;
+    let mut property_return: *mut u8 = &mut 0;
+    let mut atoms = vec![];
+    if get_window_property(
+        app.core.display,
+        win,
+        prop,
+        0,
+        1024,
+        false,
+        XA_ATOM,
+        &mut dummy_atom,
+        &mut dummy_int,
+        &mut items,
+        &mut dummy_long,
+        &mut property_return,
+    ) == Success as i32
+        && property_return as usize != 0
+    {
+        unsafe {
+            // Format 32 properties are returned as array of longs
+            let list = std::slice::from_raw_parts(property_return as *const Atom, items as usize);
+            atoms.extend_from_slice(list);
+            x11::xlib::XFree(property_return as *mut libc::c_void)
+        };
+    }
+    atoms
+}
+
+/// ICCCM window states
+pub const WITHDRAWN_STATE: i64 = 0;
+pub const NORMAL_STATE: i64 = 1;
+pub const ICONIC_STATE: i64 = 3;
+
+/// Set ICCCM `WM_STATE` of window
+pub fn set_wm_state(app: &mut Application, win: u64, state: i64) {
+    let data: [i64; 2] = [state, 0];
+    change_property(
+        app.core.display,
+        win,
+        app.atoms.wm_state,
+        app.atoms.wm_state,
+        32,
+        PropModeReplace,
+        &data as *const [i64; 2] as *mut u8,
+        2,
+    );
+}
+
+/// Set `_NET_WM_STATE` of window from client flags
+///
+/// Only states managed by window manager are changed, others set by client are kept
+pub fn update_client_state(app: &mut Application, win: u64) {
+    if let Some((s, w, c)) = find_window_indexes(app, win) {
+        let owned = [
+            app.atoms.net_wm_fullscreen,
+            app.atoms.net_wm_state_sticky,
+            app.atoms.net_wm_state_hidden,
+        ];
+        let mut state = get_atom_list_prop(app, win, app.atoms.net_wm_state);
+        state.retain(|a| !owned.contains(a));
+        let client = &app.runtime.screens[s].workspaces[w].clients[c];
+        if client.fullscreen {
+            state.push(app.atoms.net_wm_fullscreen);
+        }
+        if client.sticky {
+            state.push(app.atoms.net_wm_state_sticky);
+        }
+        if client.minimized {
+            state.push(app.atoms.net_wm_state_hidden);
+        }
+        change_property(
+            app.core.display,
+            win,
+            app.atoms.net_wm_state,
+            XA_ATOM,
+            32,
+            PropModeReplace,
+            state.as_mut_ptr() as *mut u8,
+            state.len() as i32,
+        );
+    }
+}
+
 /// Updates client list property of WM
 /// 1. Delete present list
 /// 2. For every client on every workspace on every screen add client to list
@@ -172,6 +301,19 @@ pub fn update_client_list(app: &mut Application) {
             }
         }
     }
+    // Hidden scratchpads are still managed
+    for client in &app.runtime.scratchpads {
+        change_property(
+            app.core.display,
+            app.core.root_win,
+            app.atoms.net_client_list,
+            XA_WINDOW,
+            32,
+            PropModeAppend,
+            &client.window_id as *const u64 as *mut u8,
+            1,
+        );
+    }
 }
 
 /// Safely sends atom to X server
@@ -233,10 +375,15 @@ pub fn update_normal_hints(app: &mut Application, c: &mut Client) {
 
 /// Shows all windows on current workspace
 pub fn show_workspace(app: &mut Application, screen: usize, workspace: usize) {
+    let (screen_index, workspace_index) = (screen, workspace);
     let screen = &mut app.runtime.screens[screen];
     let workspace = &mut screen.workspaces.get_mut(workspace).unwrap();
     // Iterate over all clients
     for client in &mut workspace.clients {
+        // Minimized clients stay out of view
+        if client.minimized {
+            continue;
+        }
         // 10. Fullscreen window if needed
         if client.fullscreen {
             move_resize_window(
@@ -269,19 +416,155 @@ pub fn show_workspace(app: &mut Application, screen: usize, workspace: usize) {
         };
         client.visible = true;
     }
+    restack(app, screen_index, workspace_index);
+}
+
+/// Distance from `from` to `to` rectangle `(x, y, w, h)` in specified direction
+///
+/// Returns [`None`] if `to` is not fully in that direction
+fn direction_distance(
+    from: (i64, i64, i64, i64),
+    to: (i64, i64, i64, i64),
+    d: Direction,
+) -> Option<i64> {
+    let (fx, fy, fw, fh) = from;
+    let (tx, ty, tw, th) = to;
+    // Distance along direction
+    let axis = match d {
+        Direction::Left => fx - (tx + tw),
+        Direction::Right => tx - (fx + fw),
+        Direction::Up => fy - (ty + th),
+        Direction::Down => ty - (fy + fh),
+    };
+    if axis < 0 {
+        return None;
+    }
+    // Offset of centers across direction
+    let across = match d {
+        Direction::Left | Direction::Right => ((fy + fh / 2) - (ty + th / 2)).abs(),
+        Direction::Up | Direction::Down => ((fx + fw / 2) - (tx + tw / 2)).abs(),
+    };
+    Some(axis + across)
+}
+
+/// Returns index of closest tiled client in specified direction
+pub fn client_in_direction(workspace: &Workspace, index: usize, d: Direction) -> Option<usize> {
+    let rect = |c: &Client| {
+        (
+            c.x as i64,
+            c.y as i64,
+            (c.w + 2 * c.border) as i64,
+            (c.h + 2 * c.border) as i64,
+        )
+    };
+    let from = rect(workspace.clients.get(index)?);
+    workspace
+        .clients
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| *i != index && !c.floating && !c.fullscreen && !c.minimized)
+        .filter_map(|(i, c)| direction_distance(from, rect(c), d).map(|dist| (i, dist)))
+        .min_by_key(|(_, dist)| *dist)
+        .map(|(i, _)| i)
+}
+
+/// Returns index of closest screen in specified direction
+pub fn screen_in_direction(app: &Application, screen: usize, d: Direction) -> Option<usize> {
+    let rect = |s: &Screen| (s.x, s.y, s.width, s.height);
+    let from = rect(&app.runtime.screens[screen]);
+    app.runtime
+        .screens
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != screen)
+        .filter_map(|(i, s)| direction_distance(from, rect(s), d).map(|dist| (i, dist)))
+        .min_by_key(|(_, dist)| *dist)
+        .map(|(i, _)| i)
+}
+
+/// Raise focused client if clients of workspace overlap
+/// 1. Check if layout is stacked
+/// 2. Raise focused tiled client
+/// 3. Keep floating clients above it
+pub fn restack(app: &mut Application, screen: usize, workspace: usize) {
+    let workspace = &app.runtime.screens[screen].workspaces[workspace];
+    // 1. Check
+    if !LAYOUTS[workspace.layout].stacked() {
+        return;
+    }
+    // 2. Raise focused
+    if let Some(client) = workspace
+        .current_client
+        .and_then(|c| workspace.clients.get(c))
+    {
+        if !client.floating && !client.fullscreen {
+            raise_window(app.core.display, client.window_id);
+        }
+    }
+    // 3. Raise floating
+    for client in &workspace.clients {
+        if client.floating || client.fullscreen {
+            raise_window(app.core.display, client.window_id);
+        }
+    }
+}
+
+/// Gaps used by workspace: its overrides or configured ones, none if gaps disabled
+pub fn workspace_gaps(config: &Configuration, workspace: &Workspace) -> Gaps {
+    if !workspace.gaps_enabled {
+        return Gaps::default();
+    }
+    Gaps {
+        inner: workspace.inner_gap.unwrap_or(config.inner_gap_width) as i32,
+        outer: workspace.outer_gap.unwrap_or(config.outer_gap_width) as i32,
+    }
+}
+
+/// Gaps and border of tiled clients on workspace
+/// 1. Get configured gaps and border
+/// 2. Strip them if only one tiled client is visible or layout shows clients one at a time
+pub fn tiled_decorations(config: &Configuration, workspace: &Workspace) -> (Gaps, u32) {
+    // 1. Configured
+    let mut gaps = workspace_gaps(config, workspace);
+    let mut border = config.border_size as u32;
+    // 2. Smart
+    let tiled = workspace
+        .clients
+        .iter()
+        .filter(|c| !c.floating && !c.fullscreen && !c.minimized)
+        .count();
+    if tiled == 1 || LAYOUTS[workspace.layout].stacked() {
+        if config.smart_gaps {
+            gaps = Gaps::default();
+        }
+        if config.smart_borders {
+            border = 0;
+        }
+    }
+    (gaps, border)
+}
+
+/// Border of client on workspace, floating clients always have border
+pub fn client_border(config: &Configuration, workspace: &Workspace, floating: bool) -> u32 {
+    if floating {
+        config.border_size as u32
+    } else {
+        tiled_decorations(config, workspace).1
+    }
 }
 
 /// Hides all windows on current workspace
 pub fn hide_workspace(app: &mut Application, screen: usize, workspace: usize) {
-    let window_decoration_offset = app.config.gap_width + app.config.border_size;
     let screen = &mut app.runtime.screens[screen];
     let workspace = &mut screen.workspaces.get_mut(workspace).unwrap();
+    let gaps = workspace_gaps(&app.config, workspace);
+    let window_decoration_offset = gaps.outer + app.config.border_size as i32;
     // Iterate over all clients
     for client in &mut workspace.clients {
         move_resize_window(
             app.core.display,
             client.window_id,
-            -(2 * client.w as i32 + window_decoration_offset as i32),
+            -(2 * client.w as i32 + window_decoration_offset),
             0,
             client.w,
             client.h,
@@ -290,99 +573,81 @@ pub fn hide_workspace(app: &mut Application, screen: usize, workspace: usize) {
         client.visible = false;
     }
 }
-/// Arrange windows of specified workspace in specified layout
+/// Arrange windows of specified workspace using its layout
 /// 1. Get structs by index
-/// 2. Calculate usable screen sizes, gaps, borders etc
-/// 3. Get amount of clients to be tiled
-/// 4. Check if all client go to master
-/// 5. Iterate all clients in current workspace and calculate geometry
-/// 6. Show maximized clients
-/// 7. Show master clients
-/// 8. Show stack clients
-/// 9. Update calculated geometry
-/// 10. Fullscreen window if needed
-/// 11. Update borders
-/// 12. Position windows
+/// 2. Calculate usable screen area, gaps, borders etc
+/// 3. Get clients to be tiled
+/// 4. Calculate geometry using workspace layout
+/// 5. Update calculated geometry
 pub fn arrange_workspace(app: &mut Application, screen: usize, workspace: usize) {
     log!("======ARRANGING S: {}, W: {}", screen, workspace);
     // 1. Get actual structures
     let screen = &mut app.runtime.screens[screen];
     let workspace = &mut screen.workspaces[workspace];
-    // 2. Calculate usable screen sizes, gaps, borders etc
+    // 2. Calculate usable screen area, gaps, borders etc
     let bar_offsets = screen.bar_offsets;
-    let screen_height = screen.height - (bar_offsets.up + bar_offsets.down) as i64;
-    let gap = app.config.gap_width as i32;
-    let border = app.config.border_size as u32;
-    let mut master_width = ((screen.width as i32 - gap * 3) as f64 * workspace.master_width) as u32;
-    let stack_width = (screen.width as i32 - gap * 3) - master_width as i32;
-    let mut master_capacity = workspace.master_capacity;
-
-    // 3. Get amount of clients to be tiled
-    let stack_size = workspace.clients.iter().filter(|&c| !c.floating).count();
-    // 4. Check if all client go to master
-    if master_capacity <= 0 || master_capacity >= stack_size as i64 {
-        master_capacity = stack_size as i64;
-        master_width = screen.width as u32 - gap as u32 * 2;
-    }
-    log!("   |- Arranging {} tilable window", stack_size);
-    // 5. Iterate all clients in current workspace and calculate geometry
-    for (index, client) in workspace
+    let area = Rect {
+        x: (screen.x + bar_offsets.left as i64) as i32,
+        y: (screen.y + bar_offsets.up as i64) as i32,
+        w: (screen.width - (bar_offsets.left + bar_offsets.right) as i64) as u32,
+        h: (screen.height - (bar_offsets.up + bar_offsets.down) as i64) as u32,
+    };
+    let (gaps, border) = tiled_decorations(&app.config, workspace);
+    let params = LayoutParams {
+        master_capacity: workspace.master_capacity,
+        master_width: workspace.master_width,
+    };
+
+    // 3. Get clients to be tiled
+    let tiled: Vec<&mut Client> = workspace
         .clients
         .iter_mut()
         .rev()
-        .filter(|c| !c.floating && !c.fullscreen)
-        .enumerate()
-    {
-        // 6. Show maximized clients
-        if stack_size == 1 {
-            client.x = screen.x as i32;
-            client.y = screen.y as i32 + bar_offsets.up as i32;
-            client.w = screen.width as u32;
-            client.h = screen_height as u32;
-            client.border = 0;
-        } else {
-            if (index as i64) < master_capacity {
-                // 7. Show master clients
-                let win_height =
-                    (screen_height - gap as i64 - master_capacity * gap as i64) / master_capacity;
-                client.x = gap + screen.x as i32;
-                client.y = bar_offsets.up as i32
-                    + gap
-                    + (win_height as i32 + gap) * index as i32
-                    + screen.y as i32;
-                client.w = master_width - 2 * border;
-                client.h = if index as i64 != master_capacity - 1 {
-                    win_height as u32 - 2 * border
-                } else {
-                    (screen_height as i32 - gap - client.y + bar_offsets.up as i32) as u32
-                        - 2 * border
-                };
-            } else {
-                // 8. Show stack clients
-                let win_height = (screen_height
-                    - gap as i64
-                    - (stack_size as i64 - master_capacity) * gap as i64)
-                    / (stack_size as i64 - master_capacity);
-                client.x = master_width as i32 + (gap * 2) + screen.x as i32;
-                client.y = bar_offsets.up as i32
-                    + gap
-                    + (win_height as i32 + gap) * (index as i64 - master_capacity) as i32
-                    + screen.y as i32;
-                client.w = stack_width as u32 - 2 * border;
-                client.h = if index != stack_size - 1 {
-                    win_height as u32 - 2 * border
-                } else {
-                    (screen_height as i32 - gap - client.y + bar_offsets.up as i32) as u32
-                        - 2 * border
-                };
-            }
-            client.border = app.config.border_size as u32;
-        }
+        .filter(|c| !c.floating && !c.fullscreen && !c.minimized)
+        .collect();
+    let layout_clients: Vec<LayoutClient> = tiled
+        .iter()
+        .map(|c| LayoutClient {
+            minw: c.minw,
+            minh: c.minh,
+            factor: c.factor,
+        })
+        .collect();
+    log!("   |- Arranging {} tilable window", tiled.len());
+
+    // 4. Calculate geometry
+    let layout = LAYOUTS[workspace.layout];
+    let geometries = layout.arrange(area, gaps, border, &layout_clients, &params);
+
+    // 5. Update geometry
+    for (client, geometry) in tiled.into_iter().zip(geometries) {
+        client.x = geometry.x;
+        client.y = geometry.y;
+        client.w = geometry.w;
+        client.h = geometry.h;
+        client.border = geometry.border;
+    }
+}
 
-        // client.x += screen.x as i32;
-        // client.y += screen.y as i32;
+/// Publish layout names of all desktops for bars
+pub fn update_layout_info(app: &mut Application) {
+    let mut names: Vec<String> = vec![];
+    for screen in &app.runtime.screens {
+        for workspace in &screen.workspaces {
+            names.push(LAYOUTS[workspace.layout].name().to_string());
+        }
     }
-    return;
+    let mut bytes = vec_string_to_bytes(names);
+    change_property(
+        app.core.display,
+        app.core.root_win,
+        app.atoms.rtwm_desktop_layouts,
+        app.atoms.utf8string,
+        8,
+        PropModeReplace,
+        bytes.as_mut_ptr(),
+        bytes.len() as i32,
+    );
 }
 
 /// Spawn new program by forking
@@ -391,6 +656,16 @@ pub fn arrange_workspace(app: &mut Application, screen: usize, workspace: usize)
 /// 2. For child close connections from Parent
 /// 3. Spawn program using sh
 pub fn spawn<S: AsRef<CStr>>(app: &mut Application, args: &[S], rule: Option<(usize, usize)>) {
+    spawn_with_env(app, args, rule, &[]);
+}
+
+/// Same as [`spawn`], also setting environment variables of program
+pub fn spawn_with_env<S: AsRef<CStr>>(
+    app: &mut Application,
+    args: &[S],
+    rule: Option<(usize, usize)>,
+    env: &[(&str, String)],
+) {
     unsafe {
         match nix::unistd::fork() {
             Ok(nix::unistd::ForkResult::Parent { child }) => {
@@ -412,13 +687,67 @@ pub fn spawn<S: AsRef<CStr>>(app: &mut Application, args: &[S], rule: Option<(us
                     };
                 }
                 // 3. Run
+                for (key, value) in env {
+                    std::env::set_var(key, value);
+                }
                 let _ = nix::unistd::execvp(args[0].as_ref(), &args);
+                libc::_exit(1);
             }
             Err(_) => {}
         }
     }
 }
 
+/// Spawn program with piped stdin and stdout
+///
+/// 1. Create pipes closed on exec
+/// 2. For child connect pipes to stdin & stdout and close connection from parent
+/// 3. Run program
+/// 4. For parent close child ends of pipes and make own ends nonblocking
+///
+/// Returns file descriptors for writing to program stdin and reading its stdout
+pub fn spawn_piped<S: AsRef<CStr>>(app: &mut Application, args: &[S]) -> Option<(i32, i32)> {
+    use nix::fcntl::OFlag;
+    use nix::unistd::{close, dup2, pipe2};
+    // 1. Pipes
+    let (stdin_read, stdin_write) = pipe2(OFlag::O_CLOEXEC).ok()?;
+    let (stdout_read, stdout_write) = match pipe2(OFlag::O_CLOEXEC) {
+        Ok(p) => p,
+        Err(_) => {
+            let _ = close(stdin_read);
+            let _ = close(stdin_write);
+            return None;
+        }
+    };
+    unsafe {
+        match nix::unistd::fork() {
+            Ok(nix::unistd::ForkResult::Child) => {
+                // 2. Connect
+                let _ = dup2(stdin_read, 0);
+                let _ = dup2(stdout_write, 1);
+                if app.core.display as *mut x11::xlib::Display as usize != 0 {
+                    let _ = close(x11::xlib::XConnectionNumber(app.core.display));
+                }
+                // 3. Run
+                let _ = nix::unistd::execvp(args[0].as_ref(), args);
+                libc::_exit(1);
+            }
+            result => {
+                // 4. Close
+                let _ = close(stdin_read);
+                let _ = close(stdout_write);
+                if result.is_err() || !set_nonblocking(stdin_write) || !set_nonblocking(stdout_read)
+                {
+                    let _ = close(stdin_write);
+                    let _ = close(stdout_read);
+                    return None;
+                }
+                Some((stdin_write, stdout_read))
+            }
+        }
+    }
+}
+
 pub fn get_client_pid(app: &mut Application, win: u64) -> Option<i32> {
     let mut actual_type: Atom = 0;
     let mut actual_format: i32 = 0;
@@ -561,7 +890,17 @@ pub fn set_urgent(app: &mut Application, win: u64, urg: bool) {
     log!("|- Setting urgency to {urg} for {win}");
 
     if let Some((s, w, c)) = find_window_indexes(app, win) {
-        app.runtime.screens[s].workspaces[w].clients[c].urgent = urg;
+        let client = &mut app.runtime.screens[s].workspaces[w].clients[c];
+        if client.urgent != urg {
+            client.urgent = urg;
+            emit_event(
+                app,
+                Event::Urgent {
+                    window: win,
+                    urgent: urg,
+                },
+            );
+        }
     }
 
     unsafe {
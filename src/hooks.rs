@@ -0,0 +1,84 @@
+//! Commands from config spawned on window manager events
+
+use crate::helper::find_window_indexes;
+use crate::helper::get_client_class;
+use crate::helper::spawn_with_env;
+use crate::ipc::Event;
+use crate::log;
+use crate::structs::*;
+
+/// Run hooks matching event sent to IPC subscribers
+pub fn run_event_hooks(app: &mut Application, event: &Event) {
+    match *event {
+        Event::Workspace { screen, workspace } => {
+            run_hooks(
+                app,
+                HookEvent::WorkspaceChanged,
+                None,
+                screen,
+                Some(workspace),
+            );
+        }
+        Event::WindowManaged {
+            window,
+            screen,
+            workspace,
+        } => {
+            run_hooks(
+                app,
+                HookEvent::ClientManaged,
+                Some(window),
+                screen,
+                Some(workspace),
+            );
+        }
+        Event::Urgent {
+            window,
+            urgent: true,
+        } => {
+            if let Some((s, w, _)) = find_window_indexes(app, window) {
+                run_hooks(app, HookEvent::ClientUrgent, Some(window), s, Some(w));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Spawn commands of hooks for event
+/// 1. Find hooks for event
+/// 2. Collect event details
+/// 3. Spawn commands without waiting for them
+pub fn run_hooks(
+    app: &mut Application,
+    event: HookEvent,
+    window: Option<u64>,
+    screen: usize,
+    workspace: Option<usize>,
+) {
+    // 1. Find hooks for event
+    let commands: Vec<_> = app
+        .config
+        .hooks
+        .iter()
+        .filter(|h| h.event == event && !h.cmd.is_empty())
+        .map(|h| h.cmd.clone())
+        .collect();
+    if commands.is_empty() {
+        return;
+    }
+    log!("|- Running {} hooks for {:?}", commands.len(), event);
+    // 2. Collect event details
+    let mut env = vec![("RTWM_SCREEN", screen.to_string())];
+    if let Some(workspace) = workspace {
+        env.push(("RTWM_WORKSPACE", workspace.to_string()));
+    }
+    if let Some(window) = window {
+        env.push(("RTWM_WINDOW", window.to_string()));
+        let (_, class) = get_client_class(app, window);
+        env.push(("RTWM_CLASS", class.unwrap_or_default()));
+    }
+    // 3. Spawn commands without waiting for them
+    for cmd in commands {
+        spawn_with_env(app, &cmd, None, &env);
+    }
+}
@@ -0,0 +1,232 @@
+//! Protocol of IPC socket shared by window manager and rtwmctl
+//!
+//! Every request and reply is a single line of JSON
+
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::actions::ActionResult;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    /// Run action same way as key binding does
+    Action(ActionResult),
+    /// Query state of window manager
+    Get(Query),
+    /// Stream events of specified kinds after reply, all events if list is empty
+    Subscribe(Vec<EventKind>),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Query {
+    /// Screens with their workspaces and clients, same as `rtwm --dump-state`
+    Tree,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reply {
+    Ok,
+    Error(String),
+    /// State of window manager
+    Tree(Tree),
+}
+
+/// State of window manager exported through IPC
+///
+/// Built from runtime structs, so their internals can change without breaking clients
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tree {
+    pub screens: Vec<ScreenInfo>,
+    pub focused_screen: usize,
+    pub focused_window: Option<u64>,
+    pub bars: Vec<BarInfo>,
+    /// Hidden scratchpads, shown ones are listed in workspaces
+    pub scratchpads: Vec<ClientInfo>,
+    /// Minimized windows, last minimized is first
+    pub minimized: Vec<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScreenInfo {
+    pub index: usize,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    /// Space reserved by bars on each side
+    pub bar_offsets: OffsetsInfo,
+    pub current_workspace: usize,
+    pub workspaces: Vec<WorkspaceInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OffsetsInfo {
+    pub left: usize,
+    pub up: usize,
+    pub right: usize,
+    pub down: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkspaceInfo {
+    /// Index within screen
+    pub index: usize,
+    pub name: String,
+    pub layout: String,
+    pub master_capacity: i64,
+    pub master_width: f64,
+    pub inner_gap: usize,
+    pub outer_gap: usize,
+    pub gaps_enabled: bool,
+    pub focused_window: Option<u64>,
+    /// Clients in stack order, master first
+    pub clients: Vec<ClientInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClientInfo {
+    pub window: u64,
+    pub title: String,
+    /// `WM_CLASS` of window
+    pub instance: Option<String>,
+    pub class: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub floating: bool,
+    pub fullscreen: bool,
+    pub urgent: bool,
+    pub sticky: bool,
+    pub minimized: bool,
+    /// Name of scratchpad window is
+    pub scratchpad: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BarInfo {
+    pub window: u64,
+    pub x: i64,
+    pub y: i64,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Kinds of events clients can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Workspace,
+    Window,
+    Focus,
+    Title,
+    Urgent,
+    Layout,
+    Screen,
+}
+
+/// Event sent to subscribed clients, one per line
+///
+/// Screens and workspaces are indices, workspaces are counted within screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Workspace was focused
+    Workspace { screen: usize, workspace: usize },
+    /// Window was managed
+    WindowManaged {
+        window: u64,
+        screen: usize,
+        workspace: usize,
+    },
+    /// Window was unmanaged
+    WindowUnmanaged { window: u64 },
+    /// Focused window changed, `None` if nothing is focused
+    Focus { window: Option<u64> },
+    /// Title of window changed
+    Title { window: u64, title: String },
+    /// Urgency of window changed
+    Urgent { window: u64, urgent: bool },
+    /// Layout or master parameters of workspace changed
+    Layout {
+        screen: usize,
+        workspace: usize,
+        layout: String,
+        master_capacity: i64,
+        master_width: f64,
+    },
+    /// Screens were added, removed or resized
+    Screens { screens: Vec<ScreenGeometry> },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ScreenGeometry {
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+/// Path of socket for current display: `$XDG_RUNTIME_DIR/rtwm-$DISPLAY.sock`
+///
+/// Falls back to private `/tmp/rtwm-$UID` directory if runtime directory is not set
+pub fn socket_path() -> PathBuf {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(format!("/tmp/rtwm-{}", nix::unistd::getuid())),
+    };
+    let display = std::env::var("DISPLAY").unwrap_or_default();
+    dir.join(format!("rtwm-{}.sock", display))
+}
+
+/// Check that directory of socket belongs to current user and nobody else can access it,
+/// otherwise other users could replace socket and pretend to be window manager
+pub fn check_socket_dir(path: &Path) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let metadata = std::fs::metadata(dir)?;
+    if !metadata.is_dir()
+        || metadata.uid() != nix::unistd::getuid().as_raw()
+        || metadata.mode() & 0o077 != 0
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!(
+                "`{}` is not private directory of current user",
+                dir.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Send request to running window manager and wait for reply
+///
+/// Returns connection too, subscriptions keep sending events through it
+pub fn open_request(request: &Request) -> std::io::Result<(Reply, BufReader<UnixStream>)> {
+    let path = socket_path();
+    check_socket_dir(&path)?;
+    let mut stream = UnixStream::connect(path)?;
+    let mut data = serde_json::to_vec(request)?;
+    data.push(b'\n');
+    stream.write_all(&data)?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok((serde_json::from_str(&line)?, reader))
+}
+
+/// Send request to running window manager and wait for reply
+pub fn send_request(request: &Request) -> std::io::Result<Reply> {
+    open_request(request).map(|(reply, _)| reply)
+}
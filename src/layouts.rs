@@ -0,0 +1,738 @@
+//! Layouts used for arranging tiled clients
+//!
+//! Every layout takes usable area of screen and list of tiled clients and returns
+//! geometry for each of them. Layout is chosen for each workspace separately.
+
+/// Rectangle on screen
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// Calculated client geometry. Width and height are without border
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+    pub border: u32,
+}
+
+/// Info about tiled client needed by layouts
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutClient {
+    pub minw: i32,
+    pub minh: i32,
+    /// Relative size of client compared to its neighbours
+    pub factor: f64,
+}
+
+impl Default for LayoutClient {
+    fn default() -> Self {
+        LayoutClient {
+            minw: 0,
+            minh: 0,
+            factor: 1.0,
+        }
+    }
+}
+
+/// Gaps between clients and between clients and screen edges
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Gaps {
+    pub inner: i32,
+    pub outer: i32,
+}
+
+/// Workspace parameters used by layouts
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutParams {
+    pub master_capacity: i64,
+    pub master_width: f64,
+}
+
+pub trait Layout {
+    /// Name used in config and published for bars
+    fn name(&self) -> &'static str;
+
+    /// Clients are placed on top of each other, so focused one must be raised
+    fn stacked(&self) -> bool {
+        false
+    }
+
+    /// Calculate geometry for every client in `clients`, in the same order
+    fn arrange(
+        &self,
+        area: Rect,
+        gaps: Gaps,
+        border: u32,
+        clients: &[LayoutClient],
+        params: &LayoutParams,
+    ) -> Vec<Geometry>;
+}
+
+/// All available layouts. First one is used by default
+pub static LAYOUTS: [&(dyn Layout + Sync); 6] = [
+    &Tile,
+    &Monocle,
+    &Grid,
+    &BottomStack,
+    &CenteredMaster,
+    &Dwindle,
+];
+
+/// Find layout index by its name
+pub fn layout_index(name: &str) -> Option<usize> {
+    LAYOUTS.iter().position(|l| l.name() == name)
+}
+
+/// Geometry of window filling whole rectangle
+fn fill(area: Rect, border: u32) -> Geometry {
+    Geometry {
+        x: area.x,
+        y: area.y,
+        w: area.w.saturating_sub(2 * border),
+        h: area.h.saturating_sub(2 * border),
+        border,
+    }
+}
+
+/// Split rectangle into rows separated by gap, row heights are proportional to factors.
+/// Last row takes the rest
+fn split_rows(area: Rect, gap: i32, factors: &[f64]) -> Vec<Rect> {
+    split_axis(area.y, area.h, gap, factors)
+        .into_iter()
+        .map(|(y, h)| Rect { y, h, ..area })
+        .collect()
+}
+
+/// Split rectangle into columns separated by gap, column widths are proportional to factors.
+/// Last column takes the rest
+fn split_columns(area: Rect, gap: i32, factors: &[f64]) -> Vec<Rect> {
+    split_axis(area.x, area.w, gap, factors)
+        .into_iter()
+        .map(|(x, w)| Rect { x, w, ..area })
+        .collect()
+}
+
+/// Split segment starting at `start` of length `size` into parts proportional to factors
+fn split_axis(start: i32, size: u32, gap: i32, factors: &[f64]) -> Vec<(i32, u32)> {
+    let mut parts = vec![];
+    let n = factors.len();
+    if n == 0 {
+        return parts;
+    }
+    let available = (size as i32 - (n as i32 - 1) * gap) as f64;
+    let total: f64 = factors.iter().sum();
+    let mut position = start;
+    for (index, factor) in factors.iter().enumerate() {
+        let length = if index != n - 1 {
+            (available * factor / total) as i32
+        } else {
+            start + size as i32 - position
+        };
+        parts.push((position, length.max(0) as u32));
+        position += length + gap;
+    }
+    parts
+}
+
+/// Size factors of clients
+fn factors(clients: &[LayoutClient]) -> Vec<f64> {
+    clients.iter().map(|c| c.factor).collect()
+}
+
+/// Shrink rectangle by gap from every side
+fn inset(area: Rect, gap: i32) -> Rect {
+    Rect {
+        x: area.x + gap,
+        y: area.y + gap,
+        w: (area.w as i32 - 2 * gap).max(0) as u32,
+        h: (area.h as i32 - 2 * gap).max(0) as u32,
+    }
+}
+
+/// Amount of master clients and master size for `size` available pixels
+///
+/// Returns size of master area without outer gaps, if everything goes to master
+/// master takes all space
+fn master_split(clients: usize, params: &LayoutParams, size: u32, gaps: Gaps) -> (usize, i32) {
+    if params.master_capacity <= 0 || params.master_capacity >= clients as i64 {
+        (clients, size as i32 - gaps.outer * 2)
+    } else {
+        (
+            params.master_capacity as usize,
+            ((size as i32 - gaps.outer * 2 - gaps.inner) as f64 * params.master_width) as i32,
+        )
+    }
+}
+
+/// Master clients in left column, others stacked in right column
+pub struct Tile;
+
+impl Layout for Tile {
+    fn name(&self) -> &'static str {
+        "tile"
+    }
+
+    /// 1. Check if all client go to master
+    /// 2. Show master clients
+    /// 3. Show stack clients
+    fn arrange(
+        &self,
+        area: Rect,
+        gaps: Gaps,
+        border: u32,
+        clients: &[LayoutClient],
+        params: &LayoutParams,
+    ) -> Vec<Geometry> {
+        let stack_size = clients.len();
+        // 1. All in master
+        let (master_capacity, master_width) = master_split(stack_size, params, area.w, gaps);
+        let stack_width = if master_capacity == stack_size {
+            0
+        } else {
+            (area.w as i32 - gaps.outer * 2 - gaps.inner) - master_width
+        };
+        let area = inset(area, gaps.outer);
+
+        let factors = factors(clients);
+
+        // 2. Master
+        let mut rects = split_rows(
+            Rect {
+                w: master_width.max(0) as u32,
+                ..area
+            },
+            gaps.inner,
+            &factors[..master_capacity],
+        );
+        // 3. Stack
+        rects.append(&mut split_rows(
+            Rect {
+                x: area.x + master_width + gaps.inner,
+                w: stack_width.max(0) as u32,
+                ..area
+            },
+            gaps.inner,
+            &factors[master_capacity..],
+        ));
+        rects.into_iter().map(|r| fill(r, border)).collect()
+    }
+}
+
+/// Every client fills whole area, only focused one is visible
+pub struct Monocle;
+
+impl Layout for Monocle {
+    fn name(&self) -> &'static str {
+        "monocle"
+    }
+
+    fn stacked(&self) -> bool {
+        true
+    }
+
+    fn arrange(
+        &self,
+        area: Rect,
+        gaps: Gaps,
+        border: u32,
+        clients: &[LayoutClient],
+        _params: &LayoutParams,
+    ) -> Vec<Geometry> {
+        let area = inset(area, gaps.outer);
+        clients.iter().map(|_| fill(area, border)).collect()
+    }
+}
+
+/// Master clients in left column like in tile, others placed in balanced grid on the right.
+/// If all clients go to master whole area is grid
+pub struct Grid;
+
+impl Layout for Grid {
+    fn name(&self) -> &'static str {
+        "grid"
+    }
+
+    /// 1. Check if all client go to master
+    /// 2. Show master clients
+    /// 3. Show other clients in grid
+    fn arrange(
+        &self,
+        area: Rect,
+        gaps: Gaps,
+        border: u32,
+        clients: &[LayoutClient],
+        params: &LayoutParams,
+    ) -> Vec<Geometry> {
+        let n = clients.len();
+        // 1. All in master
+        let (master_capacity, master_width) = master_split(n, params, area.w, gaps);
+        let area = inset(area, gaps.outer);
+        if master_capacity == n {
+            return grid_cells(area, gaps.inner, n)
+                .into_iter()
+                .map(|r| fill(r, border))
+                .collect();
+        }
+        // 2. Master
+        let mut rects = split_rows(
+            Rect {
+                w: master_width.max(0) as u32,
+                ..area
+            },
+            gaps.inner,
+            &factors(&clients[..master_capacity]),
+        );
+        // 3. Grid
+        rects.append(&mut grid_cells(
+            Rect {
+                x: area.x + master_width + gaps.inner,
+                w: (area.w as i32 - master_width - gaps.inner).max(0) as u32,
+                ..area
+            },
+            gaps.inner,
+            n - master_capacity,
+        ));
+        rects.into_iter().map(|r| fill(r, border)).collect()
+    }
+}
+
+/// Split rectangle into `n` cells of balanced grid
+/// 1. Get amount of columns so grid is close to square
+/// 2. Fill columns, rightmost columns get one more row if cells can't be split evenly
+fn grid_cells(area: Rect, gap: i32, n: usize) -> Vec<Rect> {
+    // 1. Columns
+    let mut cols = 0;
+    while cols * cols < n {
+        cols += 1;
+    }
+    // 2. Fill
+    let mut cells = vec![];
+    for (index, column) in split_columns(area, gap, &vec![1.0; cols])
+        .into_iter()
+        .enumerate()
+    {
+        let rows = n / cols + usize::from(index >= cols - n % cols);
+        cells.append(&mut split_rows(column, gap, &vec![1.0; rows]));
+    }
+    cells
+}
+
+/// Master clients in top row, others placed in columns below
+pub struct BottomStack;
+
+impl Layout for BottomStack {
+    fn name(&self) -> &'static str {
+        "bstack"
+    }
+
+    /// 1. Check if all client go to master, master width is used as height ratio
+    /// 2. Show master clients
+    /// 3. Show stack clients
+    fn arrange(
+        &self,
+        area: Rect,
+        gaps: Gaps,
+        border: u32,
+        clients: &[LayoutClient],
+        params: &LayoutParams,
+    ) -> Vec<Geometry> {
+        let stack_size = clients.len();
+        // 1. All in master
+        let (master_capacity, master_height) = master_split(stack_size, params, area.h, gaps);
+        let stack_height = if master_capacity == stack_size {
+            0
+        } else {
+            (area.h as i32 - gaps.outer * 2 - gaps.inner) - master_height
+        };
+        let area = inset(area, gaps.outer);
+
+        let factors = factors(clients);
+
+        // 2. Master
+        let mut rects = split_columns(
+            Rect {
+                h: master_height.max(0) as u32,
+                ..area
+            },
+            gaps.inner,
+            &factors[..master_capacity],
+        );
+        // 3. Stack
+        rects.append(&mut split_columns(
+            Rect {
+                y: area.y + master_height + gaps.inner,
+                h: stack_height.max(0) as u32,
+                ..area
+            },
+            gaps.inner,
+            &factors[master_capacity..],
+        ));
+        rects.into_iter().map(|r| fill(r, border)).collect()
+    }
+}
+
+/// Master clients in middle column, others alternately placed in left and right columns
+pub struct CenteredMaster;
+
+impl Layout for CenteredMaster {
+    fn name(&self) -> &'static str {
+        "centeredmaster"
+    }
+
+    /// 1. If there is only one stack client (or none) fall back to tile
+    /// 2. Split area into three columns
+    /// 3. Show master clients
+    /// 4. Show stack clients, first one goes to the right
+    fn arrange(
+        &self,
+        area: Rect,
+        gaps: Gaps,
+        border: u32,
+        clients: &[LayoutClient],
+        params: &LayoutParams,
+    ) -> Vec<Geometry> {
+        let n = clients.len();
+        // 1. Fallback
+        let (master_capacity, _) = master_split(n, params, area.w, gaps);
+        if n - master_capacity <= 1 {
+            return Tile.arrange(area, gaps, border, clients, params);
+        }
+        // 2. Columns
+        let master_width =
+            ((area.w as i32 - gaps.outer * 2 - gaps.inner * 2) as f64 * params.master_width) as i32;
+        let side_width = (area.w as i32 - gaps.outer * 2 - gaps.inner * 2 - master_width) / 2;
+        let area = inset(area, gaps.outer);
+        let left = Rect {
+            w: side_width.max(0) as u32,
+            ..area
+        };
+        let master = Rect {
+            x: area.x + side_width + gaps.inner,
+            w: master_width.max(0) as u32,
+            ..area
+        };
+        let right = Rect {
+            x: master.x + master_width + gaps.inner,
+            w: (area.x + area.w as i32 - (master.x + master_width + gaps.inner)).max(0) as u32,
+            ..area
+        };
+
+        let factors = factors(clients);
+
+        // 3. Master
+        let mut geometries: Vec<Geometry> =
+            split_rows(master, gaps.inner, &factors[..master_capacity])
+                .into_iter()
+                .map(|r| fill(r, border))
+                .collect();
+        // 4. Stack
+        let stack_size = n - master_capacity;
+        let right_factors: Vec<f64> = factors[master_capacity..]
+            .iter()
+            .copied()
+            .step_by(2)
+            .collect();
+        let left_factors: Vec<f64> = factors[master_capacity..]
+            .iter()
+            .copied()
+            .skip(1)
+            .step_by(2)
+            .collect();
+        let mut right_rows = split_rows(right, gaps.inner, &right_factors).into_iter();
+        let mut left_rows = split_rows(left, gaps.inner, &left_factors).into_iter();
+        for index in 0..stack_size {
+            let rect = if index % 2 == 0 {
+                right_rows.next()
+            } else {
+                left_rows.next()
+            };
+            if let Some(rect) = rect {
+                geometries.push(fill(rect, border));
+            }
+        }
+        geometries
+    }
+}
+
+/// Every client takes half of remaining area, splits alternate between vertical and horizontal
+pub struct Dwindle;
+
+impl Layout for Dwindle {
+    fn name(&self) -> &'static str {
+        "dwindle"
+    }
+
+    /// 1. Give last client all remaining area
+    /// 2. Split remaining area, first split uses master width
+    /// 3. Move split so both parts fit minimal sizes of clients
+    /// 4. If area can't be split any more remaining clients share it
+    fn arrange(
+        &self,
+        area: Rect,
+        gaps: Gaps,
+        border: u32,
+        clients: &[LayoutClient],
+        params: &LayoutParams,
+    ) -> Vec<Geometry> {
+        let n = clients.len();
+        let mut geometries = vec![];
+        let mut rest = inset(area, gaps.outer);
+        for index in 0..n {
+            // 1. Last
+            if index == n - 1 {
+                geometries.push(fill(rest, border));
+                break;
+            }
+            // 2. Split
+            let vertical = index % 2 == 0;
+            let ratio = if index == 0 { params.master_width } else { 0.5 };
+            let (size, need, need_rest) = if vertical {
+                (
+                    rest.w as i32 - gaps.inner,
+                    clients[index].minw + 2 * border as i32,
+                    clients[index + 1].minw + 2 * border as i32,
+                )
+            } else {
+                (
+                    rest.h as i32 - gaps.inner,
+                    clients[index].minh + 2 * border as i32,
+                    clients[index + 1].minh + 2 * border as i32,
+                )
+            };
+            // 3. Fit
+            let first = ((size as f64 * ratio) as i32)
+                .max(need)
+                .min(size - need_rest);
+            // 4. Share
+            if first < need || first <= 0 {
+                for _ in index..n {
+                    geometries.push(fill(rest, border));
+                }
+                break;
+            }
+            if vertical {
+                geometries.push(fill(
+                    Rect {
+                        w: first as u32,
+                        ..rest
+                    },
+                    border,
+                ));
+                rest = Rect {
+                    x: rest.x + first + gaps.inner,
+                    w: (size - first) as u32,
+                    ..rest
+                };
+            } else {
+                geometries.push(fill(
+                    Rect {
+                        h: first as u32,
+                        ..rest
+                    },
+                    border,
+                ));
+                rest = Rect {
+                    y: rest.y + first + gaps.inner,
+                    h: (size - first) as u32,
+                    ..rest
+                };
+            }
+        }
+        geometries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rect = Rect {
+        x: 10,
+        y: 20,
+        w: 1000,
+        h: 800,
+    };
+    const GAPS: Gaps = Gaps {
+        inner: 5,
+        outer: 10,
+    };
+    const BORDER: u32 = 2;
+
+    fn params(master_capacity: i64) -> LayoutParams {
+        LayoutParams {
+            master_capacity,
+            master_width: 0.6,
+        }
+    }
+
+    fn arrange(layout: &dyn Layout, n: usize, master_capacity: i64) -> Vec<Geometry> {
+        let clients = vec![LayoutClient::default(); n];
+        layout.arrange(AREA, GAPS, BORDER, &clients, &params(master_capacity))
+    }
+
+    /// Geometry including border
+    fn outer(g: &Geometry) -> (i32, i32, i32, i32) {
+        (
+            g.x,
+            g.y,
+            g.x + (g.w + 2 * g.border) as i32,
+            g.y + (g.h + 2 * g.border) as i32,
+        )
+    }
+
+    fn overlap(a: &Geometry, b: &Geometry) -> bool {
+        let (ax1, ay1, ax2, ay2) = outer(a);
+        let (bx1, by1, bx2, by2) = outer(b);
+        ax1 < bx2 && bx1 < ax2 && ay1 < by2 && by1 < ay2
+    }
+
+    #[test]
+    fn every_client_gets_geometry_inside_area() {
+        let inner = inset(AREA, GAPS.outer);
+        for layout in LAYOUTS {
+            for n in [0, 1, 2, 5] {
+                for master_capacity in [-1, 0, 1, 2, 10] {
+                    let geometries = arrange(layout, n, master_capacity);
+                    assert_eq!(geometries.len(), n, "{} n={}", layout.name(), n);
+                    for g in &geometries {
+                        let (x1, y1, x2, y2) = outer(g);
+                        assert!(
+                            x1 >= inner.x
+                                && y1 >= inner.y
+                                && x2 <= inner.x + inner.w as i32
+                                && y2 <= inner.y + inner.h as i32,
+                            "{} n={} capacity={}: {:?} outside of {:?}",
+                            layout.name(),
+                            n,
+                            master_capacity,
+                            g,
+                            inner
+                        );
+                        assert_eq!(g.border, BORDER);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tiled_clients_do_not_overlap() {
+        for layout in LAYOUTS.iter().filter(|l| !l.stacked()) {
+            for n in [2, 5] {
+                for master_capacity in [0, 1, 2] {
+                    let geometries = arrange(*layout, n, master_capacity);
+                    for (i, a) in geometries.iter().enumerate() {
+                        for b in &geometries[i + 1..] {
+                            assert!(
+                                !overlap(a, b),
+                                "{} n={} capacity={}: {:?} overlaps {:?}",
+                                layout.name(),
+                                n,
+                                master_capacity,
+                                a,
+                                b
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_client_fills_area() {
+        let inner = inset(AREA, GAPS.outer);
+        for layout in LAYOUTS {
+            let g = arrange(layout, 1, 1)[0];
+            assert_eq!(
+                outer(&g),
+                (inner.x, inner.y, 1000, 810),
+                "{}",
+                layout.name()
+            );
+        }
+    }
+
+    #[test]
+    fn monocle_stacks_all_clients() {
+        let geometries = arrange(&Monocle, 5, 1);
+        assert!(Monocle.stacked());
+        assert!(geometries.iter().all(|g| outer(g) == outer(&geometries[0])));
+    }
+
+    #[test]
+    fn tile_splits_master_and_stack() {
+        let geometries = arrange(&Tile, 2, 1);
+        // (1000 - 2 * 10 - 5) * 0.6
+        assert_eq!(outer(&geometries[0]), (20, 30, 605, 810));
+        assert_eq!(outer(&geometries[1]), (610, 30, 1000, 810));
+    }
+
+    #[test]
+    fn non_positive_master_capacity_puts_everything_in_master() {
+        for master_capacity in [0, -3] {
+            let geometries = arrange(&Tile, 5, master_capacity);
+            // Single column of full width
+            assert!(geometries.iter().all(|g| g.x == 20 && outer(g).2 == 1000));
+            let geometries = arrange(&BottomStack, 5, master_capacity);
+            // Single row of full height
+            assert!(geometries.iter().all(|g| g.y == 30 && outer(g).3 == 810));
+            // Whole area is grid
+            let grid = arrange(&Grid, 5, master_capacity);
+            let mut columns: Vec<i32> = grid.iter().map(|g| g.x).collect();
+            columns.dedup();
+            assert_eq!(columns.len(), 3);
+        }
+    }
+
+    #[test]
+    fn grid_keeps_masters_in_left_column() {
+        let geometries = arrange(&Grid, 5, 1);
+        assert_eq!(outer(&geometries[0]), (20, 30, 605, 810));
+        assert!(geometries[1..].iter().all(|g| g.x >= 610));
+    }
+
+    #[test]
+    fn centered_master_falls_back_to_tile() {
+        let centered = arrange(&CenteredMaster, 2, 1);
+        let tile = arrange(&Tile, 2, 1);
+        for (a, b) in centered.iter().zip(&tile) {
+            assert_eq!(outer(a), outer(b));
+        }
+    }
+
+    #[test]
+    fn dwindle_shares_area_when_clients_do_not_fit() {
+        let mut clients = vec![LayoutClient::default(); 3];
+        clients[0].minw = 600;
+        clients[1].minw = 600;
+        let geometries = Dwindle.arrange(AREA, GAPS, BORDER, &clients, &params(1));
+        assert_eq!(geometries.len(), 3);
+        assert!(geometries.iter().all(|g| outer(g) == (20, 30, 1000, 810)));
+    }
+
+    #[test]
+    fn dwindle_moves_split_to_fit_minimal_size() {
+        let mut clients = vec![LayoutClient::default(); 2];
+        clients[1].minw = 500;
+        let geometries = Dwindle.arrange(AREA, GAPS, BORDER, &clients, &params(1));
+        // Second client needs 500 + 2 * border of 975 available pixels
+        assert_eq!(outer(&geometries[0]).2, 20 + 471);
+        assert_eq!(outer(&geometries[1]), (20 + 471 + 5, 30, 1000, 810));
+    }
+
+    #[test]
+    fn layouts_are_found_by_name() {
+        for (index, layout) in LAYOUTS.iter().enumerate() {
+            assert_eq!(layout_index(layout.name()), Some(index));
+        }
+        assert_eq!(layout_index("spiral"), None);
+    }
+}
@@ -0,0 +1,280 @@
+//! Loading user configuration from TOML file at runtime
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Deserializer;
+
+use crate::config::NUMBER_OF_DESKTOPS;
+use crate::structs::*;
+use crate::utils::*;
+
+use x11::xlib::ControlMask;
+use x11::xlib::LockMask;
+use x11::xlib::Mod1Mask;
+use x11::xlib::Mod2Mask;
+use x11::xlib::Mod3Mask;
+use x11::xlib::Mod4Mask;
+use x11::xlib::Mod5Mask;
+use x11::xlib::ShiftMask;
+
+/// Returns path to config file
+///
+/// 1. Use `$XDG_CONFIG_HOME/rtwm/config.toml`
+/// 2. Fall back to `$HOME/.config/rtwm/config.toml`
+pub fn config_path() -> Option<PathBuf> {
+    // 1. XDG
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        if !dir.is_empty() {
+            return Some(PathBuf::from(dir).join("rtwm").join("config.toml"));
+        }
+    }
+    // 2. Home
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".config")
+            .join("rtwm")
+            .join("config.toml")
+    })
+}
+
+/// Read and parse configuration from specified file
+pub fn read_config(path: &PathBuf) -> Result<Configuration, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    toml::from_str(&text).map_err(|e| format!("failed to parse {}: {}", path.display(), e))
+}
+
+/// Returns configuration used by window manager
+///
+/// 1. If there is no config file use compiled-in [`crate::config::config`]
+/// 2. If config file is broken report it and use compiled-in config
+pub fn load_config() -> Configuration {
+    let path = match config_path() {
+        Some(p) if p.exists() => p,
+        // 1. No file
+        _ => {
+            log!("|- No config file found. Using compiled-in config");
+            return Configuration::default();
+        }
+    };
+    match read_config(&path) {
+        Ok(c) => c,
+        // 2. Broken file
+        Err(e) => {
+            eprintln!("rtwm: {}. Using compiled-in config", e);
+            Configuration::default()
+        }
+    }
+}
+
+/// Convert keysym name such as `Return` or `XF86AudioMute` to keysym
+pub fn parse_keysym(name: &str) -> Option<u32> {
+    let c_name = std::ffi::CString::new(name).ok()?;
+    let keysym = unsafe { x11::xlib::XStringToKeysym(c_name.as_ptr()) };
+    if keysym == x11::xlib::NoSymbol as u64 {
+        None
+    } else {
+        Some(keysym as u32)
+    }
+}
+
+/// Convert modifier name to mask
+pub fn parse_modifier(name: &str) -> Option<u32> {
+    match name.to_lowercase().as_str() {
+        "shift" => Some(ShiftMask),
+        "lock" => Some(LockMask),
+        "control" | "ctrl" => Some(ControlMask),
+        "mod1" | "alt" => Some(Mod1Mask),
+        "mod2" => Some(Mod2Mask),
+        "mod3" => Some(Mod3Mask),
+        "mod4" | "super" => Some(Mod4Mask),
+        "mod5" => Some(Mod5Mask),
+        _ => None,
+    }
+}
+
+/// Convert key combination such as `Mod4+Shift+q` to keysym and modifier mask
+pub fn parse_key(key: &str) -> Result<(u32, u32), String> {
+    let mut parts: Vec<&str> = key.split('+').map(|p| p.trim()).collect();
+    // Last part is always key itself
+    let name = match parts.pop() {
+        Some(n) if !n.is_empty() => n,
+        _ => return Err(format!("empty key in `{}`", key)),
+    };
+    let mut modifier = 0;
+    for part in parts {
+        match parse_modifier(part) {
+            Some(m) => modifier |= m,
+            None => return Err(format!("unknown modifier `{}` in `{}`", part, key)),
+        }
+    }
+    match parse_keysym(name) {
+        Some(keysym) => Ok((keysym, modifier)),
+        None => Err(format!("unknown keysym `{}` in `{}`", name, key)),
+    }
+}
+
+/// Convert `#RRGGBB` or `#AARRGGBB` to color
+pub fn parse_color(hex: &str) -> Result<Color, String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    // `from_str_radix` accepts leading sign
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid color `{}`", hex));
+    }
+    let value = u32::from_str_radix(digits, 16).map_err(|_| format!("invalid color `{}`", hex))?;
+    match digits.len() {
+        6 => Ok(Color {
+            alpha: 255,
+            red: (value >> 16) as u8,
+            green: (value >> 8) as u8,
+            blue: value as u8,
+        }),
+        8 => Ok(Color {
+            alpha: (value >> 24) as u8,
+            red: (value >> 16) as u8,
+            green: (value >> 8) as u8,
+            blue: value as u8,
+        }),
+        _ => Err(format!("invalid color `{}`", hex)),
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(hex: String) -> Result<Self, Self::Error> {
+        parse_color(&hex)
+    }
+}
+
+impl TryFrom<KeyActionDef> for KeyAction {
+    type Error = String;
+
+    fn try_from(def: KeyActionDef) -> Result<Self, Self::Error> {
+        let (keysym, modifier) = parse_key(&def.key)?;
+        Ok(KeyAction {
+            keysym,
+            modifier,
+            result: def.result,
+        })
+    }
+}
+
+/// Deserialize list of keysym names into desktop keysyms
+pub fn deserialize_keysyms<'de, D>(deserializer: D) -> Result<[u32; NUMBER_OF_DESKTOPS], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let names: Vec<String> = Vec::deserialize(deserializer)?;
+    let mut keysyms = [0; NUMBER_OF_DESKTOPS];
+    if names.len() > NUMBER_OF_DESKTOPS {
+        return Err(serde::de::Error::custom(format!(
+            "expected at most {} desktop keysyms",
+            NUMBER_OF_DESKTOPS
+        )));
+    }
+    for (index, name) in names.iter().enumerate() {
+        keysyms[index] = match parse_keysym(name) {
+            Some(k) => k,
+            None => {
+                return Err(serde::de::Error::custom(format!(
+                    "unknown keysym `{}`",
+                    name
+                )))
+            }
+        };
+    }
+    Ok(keysyms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers() {
+        assert_eq!(parse_modifier("Shift"), Some(ShiftMask));
+        assert_eq!(parse_modifier("ctrl"), Some(ControlMask));
+        assert_eq!(parse_modifier("Control"), Some(ControlMask));
+        assert_eq!(parse_modifier("alt"), Some(Mod1Mask));
+        assert_eq!(parse_modifier("Super"), Some(Mod4Mask));
+        assert_eq!(parse_modifier("MOD4"), Some(Mod4Mask));
+        assert_eq!(parse_modifier("hyper"), None);
+    }
+
+    #[test]
+    fn parses_keysyms() {
+        assert_eq!(parse_keysym("Return"), Some(x11::keysym::XK_Return));
+        assert_eq!(parse_keysym("q"), Some(x11::keysym::XK_q));
+        assert_eq!(
+            parse_keysym("XF86AudioMute"),
+            Some(x11::keysym::XF86XK_AudioMute)
+        );
+        assert_eq!(parse_keysym("NoSuchKey"), None);
+        assert_eq!(parse_keysym("a\0b"), None);
+    }
+
+    #[test]
+    fn parses_key_combinations() {
+        assert_eq!(
+            parse_key("Mod4+Shift+q"),
+            Ok((x11::keysym::XK_q, Mod4Mask | ShiftMask))
+        );
+        assert_eq!(
+            parse_key("super + Return"),
+            Ok((x11::keysym::XK_Return, Mod4Mask))
+        );
+        assert_eq!(parse_key("F1"), Ok((x11::keysym::XK_F1, 0)));
+    }
+
+    #[test]
+    fn reports_invalid_key_combinations() {
+        assert_eq!(
+            parse_key("Hyper+q"),
+            Err("unknown modifier `Hyper` in `Hyper+q`".to_string())
+        );
+        assert_eq!(
+            parse_key("Mod4+NoSuchKey"),
+            Err("unknown keysym `NoSuchKey` in `Mod4+NoSuchKey`".to_string())
+        );
+        assert_eq!(parse_key(""), Err("empty key in ``".to_string()));
+        assert_eq!(parse_key("Mod4+"), Err("empty key in `Mod4+`".to_string()));
+    }
+
+    fn channels(color: Color) -> (u8, u8, u8, u8) {
+        (color.alpha, color.red, color.green, color.blue)
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#ff8000").map(channels), Ok((255, 255, 128, 0)));
+        assert_eq!(parse_color("00ff80").map(channels), Ok((255, 0, 255, 128)));
+        assert_eq!(
+            parse_color("#80102030").map(channels),
+            Ok((128, 16, 32, 48))
+        );
+        assert_eq!(parse_color("#00000000").map(channels), Ok((0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn reports_invalid_colors() {
+        for hex in ["#fff", "#ff80001", "#gg0000", "#", "", "#+ff000"] {
+            assert_eq!(
+                parse_color(hex).map(channels),
+                Err(format!("invalid color `{}`", hex))
+            );
+        }
+    }
+
+    #[test]
+    fn deserializes_colors() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            color: Color,
+        }
+        let wrapper: Wrapper = toml::from_str("color = \"#c0ffffff\"").unwrap();
+        assert_eq!(channels(wrapper.color), (192, 255, 255, 255));
+        assert!(toml::from_str::<Wrapper>("color = \"white\"").is_err());
+    }
+}
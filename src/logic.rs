@@ -1,17 +1,33 @@
 //! Main windows manager logic processed as response to events
 
+use std::ffi::CString;
 use std::process::exit;
+use std::time::Duration;
+use std::time::Instant;
 
 use crate::config;
 use crate::config::NUMBER_OF_DESKTOPS;
 use crate::helper::*;
+use crate::hooks::run_hooks;
+use crate::ipc::Event;
+use crate::ipc::ScreenGeometry;
+use crate::layouts::layout_index;
+use crate::layouts::LAYOUTS;
+use crate::loader::config_path;
+use crate::loader::read_config;
+use crate::server::emit_event;
+use crate::server::emit_layout;
+use crate::setup::init_actions;
 use crate::structs::*;
 use crate::utils::*;
+use crate::wrapper::sys::read_available;
+use crate::wrapper::sys::write_available;
 use crate::wrapper::xinerama::xinerama_query_screens;
 use crate::wrapper::xlib::*;
 
 use x11::xinerama::XineramaScreenInfo;
 use x11::xlib::AnyButton;
+use x11::xlib::AnyKey;
 use x11::xlib::AnyModifier;
 use x11::xlib::Button1;
 use x11::xlib::Button3;
@@ -95,17 +111,20 @@ pub fn kill_client(app: &mut Application) {
 }
 
 pub fn move_to_screen(app: &mut Application, d: ScreenSwitching) {
+    // Update index depending on supplied direction
+    let new_screen_index = match d {
+        ScreenSwitching::Next => (app.runtime.current_screen + 1) % app.runtime.screens.len(),
+        ScreenSwitching::Previous => {
+            (app.runtime.current_screen + app.runtime.screens.len() - 1) % app.runtime.screens.len()
+        }
+    };
+    move_to_screen_index(app, new_screen_index);
+}
+
+/// Move focused client to current workspace of specified screen
+pub fn move_to_screen_index(app: &mut Application, new_screen_index: usize) {
     // Check if window is selected
     if let Some(index) = app.runtime.current_client {
-        // Update index depending on supplied direction
-        let new_screen_index = match d {
-            ScreenSwitching::Next => (app.runtime.current_screen + 1) % app.runtime.screens.len(),
-            ScreenSwitching::Previous => {
-                (app.runtime.current_screen + app.runtime.screens.len() - 1)
-                    % app.runtime.screens.len()
-            }
-        };
-
         // Pop client
         let mut client = app.runtime.screens[app.runtime.current_screen].workspaces
             [app.runtime.current_workspace]
@@ -117,11 +136,6 @@ pub fn move_to_screen(app: &mut Application, d: ScreenSwitching) {
             argb_to_int(app.config.normal_border_color),
         );
 
-        // Update workspace
-        let new_workspace: usize = app.runtime.screens[new_screen_index].current_workspace
-            + new_screen_index * config::NUMBER_OF_DESKTOPS;
-        update_client_desktop(app, client.window_id, new_workspace as u64);
-
         // For floating windows change positions
         if client.floating {
             let cur_screen = &app.runtime.screens[app.runtime.current_screen];
@@ -138,10 +152,15 @@ pub fn move_to_screen(app: &mut Application, d: ScreenSwitching) {
         // Get workspace tracker(borrow checker is really mad at me)
         let nw = app.runtime.screens[new_screen_index].current_workspace;
         // Add window to stack of another display
+        let win = client.window_id;
         app.runtime.screens[new_screen_index].workspaces[nw]
             .clients
             .push(client);
 
+        // Update workspace
+        let new_workspace: usize = nw + new_screen_index * config::NUMBER_OF_DESKTOPS;
+        update_client_desktop(app, win, new_workspace as u64);
+
         // Arrange all monitors
         arrange_current(app);
         show_workspace(
@@ -157,7 +176,28 @@ pub fn move_to_screen(app: &mut Application, d: ScreenSwitching) {
     }
 }
 
+/// Queue workspace event if focused workspace differs from previous one
+fn emit_workspace_change(app: &mut Application, previous: (usize, usize)) {
+    let current = (app.runtime.current_screen, app.runtime.current_workspace);
+    if current != previous {
+        emit_event(
+            app,
+            Event::Workspace {
+                screen: current.0,
+                workspace: current.1,
+            },
+        );
+    }
+}
+
 pub fn focus_on_screen_index(app: &mut Application, n: usize) {
+    let previous = (app.runtime.current_screen, app.runtime.current_workspace);
+    switch_screen(app, n);
+    emit_workspace_change(app, previous);
+}
+
+/// Focus screen without notifying subscribers
+fn switch_screen(app: &mut Application, n: usize) {
     log!("Focusing on screen");
     if let Some(cw) = get_current_client_id(app) {
         log!("unfocusing {}", cw);
@@ -233,11 +273,6 @@ pub fn move_to_workspace(app: &mut Application, n: u64) {
                 cc.window_id,
                 argb_to_int(app.config.normal_border_color),
             );
-            let cur_workspace: usize =
-                n as usize + app.runtime.current_screen * config::NUMBER_OF_DESKTOPS;
-
-            update_client_desktop(app, cc.window_id, cur_workspace as u64);
-
             // Update current workspace layout
             arrange_current(app);
             show_workspace(
@@ -258,9 +293,13 @@ pub fn move_to_workspace(app: &mut Application, n: u64) {
             // Update tracker
             shift_current_client(app, None, None);
             // Add client to choosen workspace (will be arranged later)
+            let win = cc.window_id;
             app.runtime.screens[app.runtime.current_screen].workspaces[n as usize]
                 .clients
                 .push(cc);
+            let cur_workspace: usize =
+                n as usize + app.runtime.current_screen * config::NUMBER_OF_DESKTOPS;
+            update_client_desktop(app, win, cur_workspace as u64);
             arrange_workspace(app, app.runtime.current_screen, n as usize);
         } else {
             let cc = app.runtime.screens[app.runtime.current_screen].workspaces
@@ -284,8 +323,10 @@ pub fn move_to_workspace(app: &mut Application, n: u64) {
 }
 
 pub fn focus_on_workspace(app: &mut Application, n: u64, r: bool) {
+    // Subscribers are notified once final workspace is known
+    let previous = (app.runtime.current_screen, app.runtime.current_workspace);
     let n = if !r {
-        focus_on_screen_index(app, n as usize / config::NUMBER_OF_DESKTOPS);
+        switch_screen(app, n as usize / config::NUMBER_OF_DESKTOPS);
         n % config::NUMBER_OF_DESKTOPS as u64
     } else {
         n
@@ -306,6 +347,8 @@ pub fn focus_on_workspace(app: &mut Application, n: u64, r: bool) {
         // Update workspace index
         app.runtime.current_workspace = n as usize;
         app.runtime.screens[app.runtime.current_screen].current_workspace = n as usize;
+        // Sticky clients follow current workspace
+        move_sticky_clients(app, app.runtime.current_screen, pw, n as usize);
 
         let w = n + app.runtime.current_screen as u64 * config::NUMBER_OF_DESKTOPS as u64;
 
@@ -336,6 +379,502 @@ pub fn focus_on_workspace(app: &mut Application, n: u64, r: bool) {
         // Hide current workspace
         hide_workspace(app, app.runtime.current_screen, pw);
     }
+    emit_workspace_change(app, previous);
+}
+
+/// Move sticky clients of screen from one workspace to another
+/// 1. Take sticky clients keeping focus tracker of old workspace
+/// 2. Put them below clients of new workspace
+/// 3. Rearrange both workspaces
+fn move_sticky_clients(app: &mut Application, screen: usize, from: usize, to: usize) {
+    // 1. Take
+    let workspace = &mut app.runtime.screens[screen].workspaces[from];
+    if !workspace.clients.iter().any(|c| c.sticky) {
+        return;
+    }
+    let focused = workspace
+        .current_client
+        .map(|c| workspace.clients[c].window_id);
+    let (sticky, rest): (Vec<Client>, Vec<Client>) =
+        workspace.clients.drain(..).partition(|c| c.sticky);
+    workspace.clients = rest;
+    workspace.current_client = focused
+        .and_then(|win| workspace.clients.iter().position(|c| c.window_id == win))
+        .or(workspace.clients.len().checked_sub(1));
+
+    // 2. Put
+    let workspace = &mut app.runtime.screens[screen].workspaces[to];
+    let moved = sticky.len();
+    workspace.clients.splice(0..0, sticky);
+    workspace.current_client = Some(workspace.current_client.map_or(0, |c| c + moved));
+
+    // 3. Rearrange
+    arrange_workspace(app, screen, from);
+    arrange_workspace(app, screen, to);
+}
+
+/// Show client on every workspace of its screen
+pub fn set_sticky(app: &mut Application, win: u64, sticky: bool) {
+    if let Some((s, w, c)) = find_window_indexes(app, win) {
+        app.runtime.screens[s].workspaces[w].clients[c].sticky = sticky;
+        update_client_state(app, win);
+        update_client_desktop(app, win, (w + s * NUMBER_OF_DESKTOPS) as u64);
+    }
+}
+
+pub fn toggle_sticky(app: &mut Application) {
+    if let Some(win) = get_current_client_id(app) {
+        let sticky = app.runtime.screens[app.runtime.current_screen].workspaces
+            [app.runtime.current_workspace]
+            .clients
+            .iter()
+            .any(|c| c.window_id == win && c.sticky);
+        set_sticky(app, win, !sticky);
+    }
+}
+
+/// Minimize client or restore it
+/// 1. Update flag & stack of minimized windows
+/// 2. Update ICCCM & EWMH state
+/// 3. Rearrange workspace, move minimized client out of view
+/// 4. Focus restored client or move focus from minimized one
+pub fn set_minimized(app: &mut Application, win: u64, minimized: bool) {
+    let (s, w, c) = match find_window_indexes(app, win) {
+        Some(indexes) => indexes,
+        None => return,
+    };
+    // 1. Flag
+    let client = &mut app.runtime.screens[s].workspaces[w].clients[c];
+    if client.minimized == minimized {
+        return;
+    }
+    client.minimized = minimized;
+    let (width, height) = (client.w, client.h);
+    app.runtime.minimized.retain(|m| *m != win);
+    if minimized {
+        app.runtime.minimized.push(win);
+    }
+
+    // 2. State
+    let state = if minimized {
+        ICONIC_STATE
+    } else {
+        NORMAL_STATE
+    };
+    set_wm_state(app, win, state);
+    update_client_state(app, win);
+
+    // 3. Rearrange
+    arrange_workspace(app, s, w);
+    if minimized {
+        move_resize_window(app.core.display, win, -2 * width as i32, 0, width, height);
+        app.runtime.screens[s].workspaces[w].clients[c].visible = false;
+    }
+    if app.runtime.screens[s].current_workspace == w {
+        show_workspace(app, s, w);
+    }
+
+    // 4. Focus
+    let current = (s, w) == (app.runtime.current_screen, app.runtime.current_workspace);
+    if !minimized {
+        if current {
+            keyboard_focus(app, win);
+        }
+    } else if app.runtime.screens[s].workspaces[w].current_client == Some(c) {
+        if current {
+            unfocus(app, win);
+        }
+        let workspace = &mut app.runtime.screens[s].workspaces[w];
+        workspace.current_client = workspace.clients.iter().rposition(|c| !c.minimized);
+        if current {
+            app.runtime.current_client = workspace.current_client;
+            match get_current_client_id(app) {
+                Some(other) => focus(app, other),
+                None => update_active_window(app),
+            }
+        }
+    }
+}
+
+pub fn minimize(app: &mut Application) {
+    if let Some(win) = get_current_client_id(app) {
+        set_minimized(app, win, true);
+    }
+}
+
+/// Restore last minimized client of current workspace
+pub fn restore_last(app: &mut Application) {
+    let workspace =
+        &app.runtime.screens[app.runtime.current_screen].workspaces[app.runtime.current_workspace];
+    let last = app
+        .runtime
+        .minimized
+        .iter()
+        .rev()
+        .find(|win| workspace.clients.iter().any(|c| c.window_id == **win))
+        .copied();
+    if let Some(win) = last {
+        set_minimized(app, win, false);
+    }
+}
+
+/// Show menu with all managed clients, chosen one is handled by [`read_window_picker`]
+/// 1. Collect "window  screen:workspace  class  title" lines, window id tells chosen line apart
+/// 2. Start menu program
+/// 3. Queue lines for menu and send what it accepts right away
+pub fn pick_window(app: &mut Application, pull: bool) {
+    if app.runtime.picker.is_some() {
+        return;
+    }
+    // 1. Collect
+    let mut entries = vec![];
+    let mut input = String::new();
+    for (s, screen) in app.runtime.screens.iter().enumerate() {
+        for (w, workspace) in screen.workspaces.iter().enumerate() {
+            for client in &workspace.clients {
+                // Every entry must stay on its own line
+                let class = client.class.clone().unwrap_or_default();
+                input += &format!(
+                    "{}  {}:{}  {}  {}\n",
+                    client.window_id,
+                    s,
+                    w,
+                    class.replace(['\n', '\r'], " "),
+                    client.window_name.replace(['\n', '\r'], " ")
+                );
+                entries.push(client.window_id);
+            }
+        }
+    }
+    // 2. Start
+    let cmd = app.config.window_picker.clone();
+    if cmd.is_empty() {
+        return;
+    }
+    let (stdin, stdout) = match spawn_piped(app, &cmd) {
+        Some(fds) => fds,
+        None => {
+            eprintln!("rtwm: failed to start window picker");
+            return;
+        }
+    };
+    // 3. Send
+    app.runtime.picker = Some(WindowPicker {
+        stdin: Some(stdin),
+        input: input.into_bytes(),
+        stdout,
+        output: vec![],
+        entries,
+        pull,
+    });
+    feed_window_picker(app);
+}
+
+/// Write queued entries to window picker menu, stdin is closed once all are written
+pub fn feed_window_picker(app: &mut Application) {
+    let picker = match &mut app.runtime.picker {
+        Some(p) => p,
+        None => return,
+    };
+    let fd = match picker.stdin {
+        Some(fd) => fd,
+        None => return,
+    };
+    match write_available(fd, &picker.input) {
+        Some(n) => {
+            picker.input.drain(..n);
+        }
+        // Menu does not read entries any more
+        None => picker.input.clear(),
+    }
+    if picker.input.is_empty() {
+        let _ = nix::unistd::close(fd);
+        picker.stdin = None;
+    }
+}
+
+/// Read output of window picker menu, choice is handled once first line is complete
+/// or menu closes its output
+pub fn read_window_picker(app: &mut Application) {
+    let picker = match &mut app.runtime.picker {
+        Some(p) => p,
+        None => return,
+    };
+    let closed = read_available(picker.stdout, &mut picker.output);
+    // Menu printing endless line is treated as if it has answered
+    if !closed && !picker.output.contains(&b'\n') && picker.output.len() < 64 * 1024 {
+        return;
+    }
+    finish_window_pick(app);
+}
+
+/// Handle window chosen in window picker menu
+/// 1. Stop talking to menu and get chosen line
+/// 2. Find chosen client
+/// 3. Pull it to current workspace or jump to it
+fn finish_window_pick(app: &mut Application) {
+    // 1. Stop
+    let picker = match app.runtime.picker.take() {
+        Some(p) => p,
+        None => return,
+    };
+    if let Some(fd) = picker.stdin {
+        let _ = nix::unistd::close(fd);
+    }
+    let _ = nix::unistd::close(picker.stdout);
+    let output = String::from_utf8_lossy(&picker.output);
+    let choice = output.lines().next().unwrap_or_default();
+
+    // 2. Find
+    let win = match choice.split_whitespace().next().map(str::parse::<u64>) {
+        Some(Ok(win)) if picker.entries.contains(&win) => win,
+        _ => return,
+    };
+    let (s, w, c) = match find_window_indexes(app, win) {
+        Some(indexes) => indexes,
+        None => return,
+    };
+
+    // 3. Pull or jump
+    if picker.pull {
+        pull_client(app, s, w, c);
+    }
+    jump_to_client(app, win);
+}
+
+/// Focus screen & workspace of client, restore and focus it
+pub fn jump_to_client(app: &mut Application, win: u64) {
+    if let Some((s, w, _)) = find_window_indexes(app, win) {
+        focus_on_workspace(app, (w + s * NUMBER_OF_DESKTOPS) as u64, false);
+        set_minimized(app, win, false);
+        keyboard_focus(app, win);
+    }
+}
+
+/// Focus client with specified class or spawn program if there is none
+///
+/// If focused client already matches, next matching one is focused
+pub fn run_or_raise(app: &mut Application, class: &str, cmd: &[CString]) {
+    // Find all matching clients
+    let mut windows = vec![];
+    for screen in &app.runtime.screens {
+        for workspace in &screen.workspaces {
+            for client in &workspace.clients {
+                windows.push(client.window_id);
+            }
+        }
+    }
+    let matching: Vec<u64> = windows
+        .into_iter()
+        .filter(|win| {
+            let (instance, client_class) = get_client_class(app, *win);
+            instance.as_deref() == Some(class) || client_class.as_deref() == Some(class)
+        })
+        .collect();
+    if matching.is_empty() {
+        spawn(app, cmd, None);
+        return;
+    }
+    // Cycle if already focused
+    let next =
+        match get_current_client_id(app).and_then(|cur| matching.iter().position(|w| *w == cur)) {
+            Some(index) => matching[(index + 1) % matching.len()],
+            None => matching[0],
+        };
+    jump_to_client(app, next);
+}
+
+/// Move client to current workspace of focused screen
+fn pull_client(app: &mut Application, s: usize, w: usize, c: usize) {
+    let (cs, cw) = (app.runtime.current_screen, app.runtime.current_workspace);
+    if (s, w) == (cs, cw) {
+        return;
+    }
+    if let Some(old) = get_current_client_id(app) {
+        unfocus(app, old);
+    }
+    // Take client from its workspace
+    let mut client = app.runtime.screens[s].workspaces[w].clients.remove(c);
+    let win = client.window_id;
+    shift_current_client(app, Some(s), Some(w));
+    if client.floating && s != cs {
+        center_client(&mut client, &app.runtime.screens[cs]);
+    }
+    arrange_workspace(app, s, w);
+    if app.runtime.screens[s].current_workspace == w {
+        show_workspace(app, s, w);
+    }
+    // Put it on current one
+    app.runtime.screens[cs].workspaces[cw].clients.push(client);
+    update_client_desktop(app, win, (cw + cs * NUMBER_OF_DESKTOPS) as u64);
+    arrange_workspace(app, cs, cw);
+    show_workspace(app, cs, cw);
+}
+
+/// Move focus to window chosen with keyboard
+/// 1. Unfocus current client
+/// 2. Focus new one
+/// 3. Raise floating client
+/// 4. Warp pointer if needed
+pub fn keyboard_focus(app: &mut Application, win: u64) {
+    if let Some((s, w, c)) = find_window_indexes(app, win) {
+        let client = &app.runtime.screens[s].workspaces[w].clients[c];
+        let (floating, width, height) = (client.floating, client.w, client.h);
+        // 1. Unfocus
+        if let Some(old) = get_current_client_id(app) {
+            unfocus(app, old);
+        }
+        // 2. Focus
+        focus(app, win);
+        // 3. Raise
+        if floating {
+            raise_window(app.core.display, win);
+        }
+        // 4. Warp
+        if app.config.warp_pointer {
+            warp_pointer_win(app.core.display, win, width as i32 / 2, height as i32 / 2);
+        }
+    }
+}
+
+/// Move focus along clients of current workspace, wrapping around
+pub fn cycle_stack(app: &mut Application, i: i64) {
+    let workspace =
+        &app.runtime.screens[app.runtime.current_screen].workspaces[app.runtime.current_workspace];
+    if workspace.clients.is_empty() {
+        return;
+    }
+    let len = workspace.clients.len() as i64;
+    // Step is wrapped too so arbitrary values from IPC can't overflow
+    let i = i.rem_euclid(len);
+    let start = workspace.current_client.map_or(-i, |index| index as i64);
+    // Skip minimized clients
+    let next = match (1..=len)
+        .map(|k| (start + i * k).rem_euclid(len) as usize)
+        .find(|&c| !workspace.clients[c].minimized)
+    {
+        Some(next) => next,
+        None => return,
+    };
+    if workspace.current_client != Some(next) {
+        let win = workspace.clients[next].window_id;
+        keyboard_focus(app, win);
+    }
+}
+
+/// Focus tiled client next to focused one in specified direction
+///
+/// If there is no such client focus adjacent screen
+pub fn focus_direction(app: &mut Application, d: Direction) {
+    let (s, w) = (app.runtime.current_screen, app.runtime.current_workspace);
+    let neighbour = app
+        .runtime
+        .current_client
+        .and_then(|index| client_in_direction(&app.runtime.screens[s].workspaces[w], index, d));
+    if let Some(other) = neighbour {
+        let win = app.runtime.screens[s].workspaces[w].clients[other].window_id;
+        keyboard_focus(app, win);
+    } else if let Some(screen) = screen_in_direction(app, s, d) {
+        focus_on_screen_index(app, screen);
+    }
+}
+
+/// Swap focused client with tiled client next to it in specified direction
+///
+/// If there is no such client move focused client to adjacent screen
+pub fn swap_direction(app: &mut Application, d: Direction) {
+    let (s, w) = (app.runtime.current_screen, app.runtime.current_workspace);
+    let index = match app.runtime.current_client {
+        Some(index) => index,
+        None => return,
+    };
+    if let Some(other) = client_in_direction(&app.runtime.screens[s].workspaces[w], index, d) {
+        let workspace = &mut app.runtime.screens[s].workspaces[w];
+        workspace.clients.swap(index, other);
+        // Update trackers
+        workspace.current_client = Some(other);
+        app.runtime.current_client = Some(other);
+        // Rearrange windows
+        arrange_current(app);
+        show_workspace(app, s, w);
+        drain_enter_events(app.core.display);
+    } else if let Some(screen) = screen_in_direction(app, s, d) {
+        move_to_screen_index(app, screen);
+    }
+}
+
+/// Swap focused client with its neighbour in stack, keeping focus on it
+pub fn move_in_stack(app: &mut Application, i: i64) {
+    let workspace = &mut app.runtime.screens[app.runtime.current_screen].workspaces
+        [app.runtime.current_workspace];
+    let index = match workspace.current_client {
+        Some(index) if workspace.clients.len() > 1 => index,
+        _ => return,
+    };
+    let len = workspace.clients.len() as i64;
+    let other = (index as i64 + i.rem_euclid(len)).rem_euclid(len) as usize;
+    workspace.clients.swap(index, other);
+    // Update trackers
+    workspace.current_client = Some(other);
+    app.runtime.current_client = Some(other);
+    // Rearrange windows
+    arrange_current(app);
+    show_workspace(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
+    // Don't let focus follow windows moved under pointer
+    drain_enter_events(app.core.display);
+}
+
+/// Swap focused client with master, or master with next client if master is focused
+/// 1. Get tiled clients in layout order
+/// 2. Pick client to swap with
+/// 3. Swap
+/// 4. Focus client which became master
+pub fn zoom(app: &mut Application) {
+    let workspace = &mut app.runtime.screens[app.runtime.current_screen].workspaces
+        [app.runtime.current_workspace];
+    let index = match workspace.current_client {
+        Some(index) => index,
+        None => return,
+    };
+    // 1. Tiled clients, layouts iterate clients in reverse
+    let tiled: Vec<usize> = (0..workspace.clients.len())
+        .rev()
+        .filter(|&i| {
+            let c = &workspace.clients[i];
+            !c.floating && !c.fullscreen && !c.minimized
+        })
+        .collect();
+    if !tiled.contains(&index) {
+        return;
+    }
+    // 2. Pick
+    let master = tiled[0];
+    let other = if index == master {
+        match tiled.get(1) {
+            Some(next) => *next,
+            None => return,
+        }
+    } else {
+        index
+    };
+    // 3. Swap
+    let old = workspace.clients[index].window_id;
+    workspace.clients.swap(master, other);
+    let win = workspace.clients[master].window_id;
+    arrange_current(app);
+    show_workspace(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
+    // 4. Focus
+    unfocus(app, old);
+    focus(app, win);
+    drain_enter_events(app.core.display);
 }
 
 pub fn update_master_width(app: &mut Application, w: f64) {
@@ -346,6 +885,60 @@ pub fn update_master_width(app: &mut Application, w: f64) {
     if f64::abs(w) < *mw + w && *mw + w < 1.0 {
         *mw += w;
     }
+    emit_layout(app);
+    // Rearrange windows
+    arrange_current(app);
+    show_workspace(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
+}
+
+pub fn update_client_factor(app: &mut Application, f: f64) {
+    // Update factor of focused client
+    if let Some(c) = app.runtime.current_client {
+        let factor = &mut app.runtime.screens[app.runtime.current_screen].workspaces
+            [app.runtime.current_workspace]
+            .clients[c]
+            .factor;
+        *factor = (*factor + f).clamp(0.25, 4.0);
+        // Rearrange windows
+        arrange_current(app);
+        show_workspace(
+            app,
+            app.runtime.current_screen,
+            app.runtime.current_workspace,
+        );
+    }
+}
+
+/// Largest gap set at runtime, keeps layout arithmetic from overflowing
+const MAX_GAP: i64 = 1000;
+
+pub fn update_gaps(app: &mut Application, i: i64) {
+    // Change gaps of current workspace starting from configured ones
+    let workspace = &mut app.runtime.screens[app.runtime.current_screen].workspaces
+        [app.runtime.current_workspace];
+    let inner = workspace.inner_gap.unwrap_or(app.config.inner_gap_width) as i64;
+    let outer = workspace.outer_gap.unwrap_or(app.config.outer_gap_width) as i64;
+    workspace.inner_gap = Some(inner.saturating_add(i).clamp(0, MAX_GAP) as usize);
+    workspace.outer_gap = Some(outer.saturating_add(i).clamp(0, MAX_GAP) as usize);
+    workspace.gaps_enabled = true;
+    // Rearrange windows
+    arrange_current(app);
+    show_workspace(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
+}
+
+pub fn toggle_gaps(app: &mut Application) {
+    // Flip gaps of current workspace
+    let workspace = &mut app.runtime.screens[app.runtime.current_screen].workspaces
+        [app.runtime.current_workspace];
+    workspace.gaps_enabled = !workspace.gaps_enabled;
     // Rearrange windows
     arrange_current(app);
     show_workspace(
@@ -357,8 +950,40 @@ pub fn update_master_width(app: &mut Application, w: f64) {
 
 pub fn update_master_capacity(app: &mut Application, i: i64) {
     // Change master size
+    let capacity = &mut app.runtime.screens[app.runtime.current_screen].workspaces
+        [app.runtime.current_workspace]
+        .master_capacity;
+    *capacity = capacity.saturating_add(i);
+    emit_layout(app);
+    // Rearrange windows
+    arrange_current(app);
+    show_workspace(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
+}
+
+/// Set layout of current workspace by name
+pub fn set_layout(app: &mut Application, name: &str) {
+    match layout_index(name) {
+        Some(index) => apply_layout(app, index),
+        None => eprintln!("rtwm: unknown layout `{}`", name),
+    }
+}
+
+/// Switch current workspace to next layout
+pub fn cycle_layout(app: &mut Application) {
+    let index = app.runtime.screens[app.runtime.current_screen].workspaces
+        [app.runtime.current_workspace]
+        .layout;
+    apply_layout(app, (index + 1) % LAYOUTS.len());
+}
+
+fn apply_layout(app: &mut Application, index: usize) {
     app.runtime.screens[app.runtime.current_screen].workspaces[app.runtime.current_workspace]
-        .master_capacity += i;
+        .layout = index;
+    emit_layout(app);
     // Rearrange windows
     arrange_current(app);
     show_workspace(
@@ -366,20 +991,20 @@ pub fn update_master_capacity(app: &mut Application, i: i64) {
         app.runtime.current_screen,
         app.runtime.current_workspace,
     );
+    update_layout_info(app);
 }
 
 pub fn toggle_float(app: &mut Application) {
     if let Some(c) = app.runtime.current_client {
-        let client = &mut app.runtime.screens[app.runtime.current_screen].workspaces
-            [app.runtime.current_workspace]
-            .clients[c];
-        client.floating = !client.floating;
-
-        client.border = if client.floating {
-            app.config.border_size as u32
-        } else {
-            0
-        };
+        let workspace = &mut app.runtime.screens[app.runtime.current_screen].workspaces
+            [app.runtime.current_workspace];
+        // Scratchpads are never tiled
+        if workspace.clients[c].scratchpad.is_some() {
+            return;
+        }
+        workspace.clients[c].floating = !workspace.clients[c].floating;
+        workspace.clients[c].border =
+            client_border(&app.config, workspace, workspace.clients[c].floating);
 
         arrange_current(app);
         show_workspace(
@@ -390,9 +1015,122 @@ pub fn toggle_float(app: &mut Application) {
     }
 }
 
+/// Returns screen, workspace and client indexes of shown scratchpad
+fn find_shown_scratchpad(app: &Application, name: &str) -> Option<(usize, usize, usize)> {
+    for (s, screen) in app.runtime.screens.iter().enumerate() {
+        for (w, workspace) in screen.workspaces.iter().enumerate() {
+            for (c, client) in workspace.clients.iter().enumerate() {
+                if client.scratchpad.as_deref() == Some(name) {
+                    return Some((s, w, c));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Name of configured scratchpad window belongs to
+///
+/// Window matches rule by instance and class, only if there is no client for that scratchpad yet
+pub fn match_scratchpad(app: &mut Application, win: u64) -> Option<String> {
+    let (instance, class) = get_client_class(app, win);
+    app.config
+        .scratchpads
+        .iter()
+        .filter(|rule| rule.instance.is_some() || rule.class.is_some())
+        .filter(|rule| rule.instance.is_none() || rule.instance == instance)
+        .filter(|rule| rule.class.is_none() || rule.class == class)
+        .map(|rule| rule.name.clone())
+        .find(|name| {
+            find_shown_scratchpad(app, name).is_none()
+                && !app
+                    .runtime
+                    .scratchpads
+                    .iter()
+                    .any(|c| c.scratchpad.as_ref() == Some(name))
+        })
+}
+
+/// How long spawned scratchpad may take to map its window before it can be spawned again
+const SCRATCHPAD_SPAWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Show or hide scratchpad on focused screen
+/// 1. Hide scratchpad if it is shown on current workspace
+/// 2. Take scratchpad from other workspace or from hidden ones
+/// 3. Spawn program if scratchpad is not running and not being started
+/// 4. Show scratchpad centered on current workspace
+pub fn toggle_scratchpad(app: &mut Application, name: &str) {
+    let (cs, cw) = (app.runtime.current_screen, app.runtime.current_workspace);
+    let focused = get_current_client_id(app);
+    let mut client = if let Some((s, w, c)) = find_shown_scratchpad(app, name) {
+        let mut client = app.runtime.screens[s].workspaces[w].clients.remove(c);
+        shift_current_client(app, Some(s), Some(w));
+        // 1. Hide
+        if (s, w) == (cs, cw) {
+            unfocus(app, client.window_id);
+            move_resize_window(
+                app.core.display,
+                client.window_id,
+                -(2 * client.w as i32 + client.border as i32),
+                0,
+                client.w,
+                client.h,
+            );
+            client.visible = false;
+            app.runtime.scratchpads.push(client);
+            if let Some(win) = get_current_client_id(app) {
+                focus(app, win);
+            }
+            return;
+        }
+        // 2. Other workspace
+        client
+    } else if let Some(index) = app
+        .runtime
+        .scratchpads
+        .iter()
+        .position(|c| c.scratchpad.as_deref() == Some(name))
+    {
+        // 2. Hidden
+        app.runtime.scratchpads.remove(index)
+    } else {
+        // 3. Spawn
+        let pending = &mut app.runtime.pending_scratchpads;
+        pending.retain(|(_, spawned)| spawned.elapsed() < SCRATCHPAD_SPAWN_TIMEOUT);
+        if pending.iter().any(|(n, _)| n == name) {
+            log!("|- Scratchpad `{}` is still starting", name);
+            return;
+        }
+        match app.config.scratchpads.iter().find(|r| r.name == name) {
+            Some(rule) => {
+                let cmd = rule.cmd.clone();
+                spawn(app, &cmd, None);
+                app.runtime
+                    .pending_scratchpads
+                    .push((name.to_string(), Instant::now()));
+            }
+            None => eprintln!("rtwm: unknown scratchpad `{}`", name),
+        }
+        return;
+    };
+
+    // 4. Show
+    if let Some(old) = focused {
+        unfocus(app, old);
+    }
+    let win = client.window_id;
+    client.floating = true;
+    client.border = app.config.border_size as u32;
+    center_client(&mut client, &app.runtime.screens[cs]);
+    app.runtime.screens[cs].workspaces[cw].clients.push(client);
+    update_client_desktop(app, win, (cw + cs * NUMBER_OF_DESKTOPS) as u64);
+    show_workspace(app, cs, cw);
+    keyboard_focus(app, win);
+}
+
 /// Get name from x server for specified window and undate it in struct
 /// 1. Get name property
-/// 2. Set window name if window is managed
+/// 2. Set window name if window is managed and name has changed
 pub fn update_client_name(app: &mut Application, win: u64) {
     // 1. Get
     let name = match get_text_property(app.core.display, win, app.atoms.net_wm_name) {
@@ -402,7 +1140,18 @@ pub fn update_client_name(app: &mut Application, win: u64) {
 
     // 2. Set
     if let Some((s, w, c)) = find_window_indexes(app, win) {
-        app.runtime.screens[s].workspaces[w].clients[c].window_name = name;
+        let client = &mut app.runtime.screens[s].workspaces[w].clients[c];
+        if client.window_name == name {
+            return;
+        }
+        client.window_name = name.clone();
+        emit_event(
+            app,
+            Event::Title {
+                window: win,
+                title: name,
+            },
+        );
     }
 }
 
@@ -432,6 +1181,11 @@ pub fn focus(app: &mut Application, win: u64) {
     set_input_focus(app.core.display, win, RevertToPointerRoot, CurrentTime);
     grab_button(app.core.display, win, Button1, ModKey);
     grab_button(app.core.display, win, Button3, ModKey);
+    restack(
+        app,
+        app.runtime.current_screen,
+        app.runtime.current_workspace,
+    );
 
     let w = app.runtime.current_workspace + app.runtime.current_screen * NUMBER_OF_DESKTOPS;
 
@@ -500,6 +1254,8 @@ pub fn arrange_all(app: &mut Application) {
 /// 2. Add more screens if amount of new screens is larger than amount of existing screens
 /// 3. Init newly created screens
 /// 4. Move everything from exceeding screens and delete them
+/// 5. Run hooks & notify subscribers about new screen layout, unless screens are set up
+///    first time on start
 pub fn update_screens(app: &mut Application) {
     // 1. Get screens
     let n = app.runtime.screens.len();
@@ -557,14 +1313,38 @@ pub fn update_screens(app: &mut Application) {
             let removed_workspaces = removed_screen.workspaces;
             for (index, workspace) in removed_workspaces.into_iter().enumerate() {
                 for client in workspace.clients {
-                    update_client_desktop(app, client.window_id, index as u64);
+                    let win = client.window_id;
                     app.runtime.screens[0].workspaces[index]
                         .clients
                         .push(client);
+                    update_client_desktop(app, win, index as u64);
                 }
             }
         }
     }
+
+    // 5. Run hooks & notify subscribers about new screen layout
+    if n == 0 {
+        return;
+    }
+    for screen in n..screens_amount {
+        run_hooks(app, HookEvent::ScreenAdded, None, screen, None);
+    }
+    for screen in screens_amount..n {
+        run_hooks(app, HookEvent::ScreenRemoved, None, screen, None);
+    }
+    let screens = app
+        .runtime
+        .screens
+        .iter()
+        .map(|s| ScreenGeometry {
+            x: s.x,
+            y: s.y,
+            width: s.width,
+            height: s.height,
+        })
+        .collect();
+    emit_event(app, Event::Screens { screens });
 }
 
 /// Create and set up workspaces
@@ -589,6 +1369,10 @@ pub fn update_desktops(app: &mut Application) {
                     current_client: None,
                     master_capacity: 1,
                     master_width: 0.5,
+                    layout: 0,
+                    inner_gap: None,
+                    outer_gap: None,
+                    gaps_enabled: true,
                 });
             }
         }
@@ -606,6 +1390,7 @@ pub fn update_desktops(app: &mut Application) {
     }
     // 4. SEt info
     update_desktop_ewmh_info(app, desktop_names_ewmh, viewports);
+    update_layout_info(app);
 }
 
 pub fn get_window_placement(app: &mut Application, win: u64, scan: bool) -> ((usize, usize), u64) {
@@ -628,6 +1413,11 @@ pub fn get_window_placement(app: &mut Application, win: u64, scan: bool) -> ((us
         );
     }
 
+    // Scratchpads are shown where they are toggled
+    if match_scratchpad(app, win).is_some() {
+        return (default_placement, 0);
+    }
+
     // Try to use previous position on startup
     if scan {
         if let Some(sw) = get_client_workspace(app, win) {
@@ -656,23 +1446,32 @@ pub fn get_window_placement(app: &mut Application, win: u64, scan: bool) -> ((us
     }
 
     // Try permanent rules
+    if let Some(rule) = find_placement_rule(app, win) {
+        let s = if let Some(s) = rule.rule_screen {
+            s
+        } else {
+            app.runtime.current_screen
+        };
+        let w = if let Some(w) = rule.rule_workspace {
+            w
+        } else {
+            app.runtime.current_workspace
+        };
+        return ((s, w), 0);
+    }
+
+    // Use current placement if nothing found;
+    return (default_placement, 0);
+}
+
+/// Returns first permanent rule matching window instance, class and title
+pub fn find_placement_rule(app: &mut Application, win: u64) -> Option<PlacementRule> {
     let title = match get_text_property(app.core.display, win, app.atoms.net_wm_name) {
         Some(name) => Some(name),
         None => None,
     };
 
-    let (instance, class) = {
-        let mut ch: x11::xlib::XClassHint = x11::xlib::XClassHint {
-            res_name: std::ptr::null_mut(),
-            res_class: std::ptr::null_mut(),
-        };
-        get_class_hint(app.core.display, win, &mut ch);
-
-        let instance = cstr_to_string(ch.res_name as *const i8);
-        let class = cstr_to_string(ch.res_class as *const i8);
-
-        (instance, class)
-    };
+    let (instance, class) = get_client_class(app, win);
 
     for rule in &app.config.placements {
         let instance_flag = {
@@ -697,20 +1496,69 @@ pub fn get_window_placement(app: &mut Application, win: u64, scan: bool) -> ((us
             }
         };
         if instance_flag && class_flag && title_flag {
-            let s = if let Some(s) = rule.rule_screen {
-                s
-            } else {
-                app.runtime.current_screen
-            };
-            let w = if let Some(w) = rule.rule_workspace {
-                w
+            return Some(rule.clone());
+        }
+    }
+    None
+}
+
+/// Re-read config file and apply it to running session
+/// 1. Read new config, keep old one if it is broken
+/// 2. Ungrab old keys & grab new ones
+/// 3. Update borders of all clients
+/// 4. Rearrange all workspaces
+/// 5. Update desktops info
+pub fn reload_config(app: &mut Application) {
+    log!("|- Reloading config");
+    // 1. Read
+    let config = match config_path() {
+        Some(path) if path.exists() => match read_config(&path) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("rtwm: {}. Keeping current config", e);
+                return;
+            }
+        },
+        _ => Configuration::default(),
+    };
+
+    // 2. Keys
+    ungrab_key(app.core.display, AnyKey, AnyModifier);
+    app.config = config;
+    init_actions(app);
+
+    // 3. Borders
+    let current_client = get_current_client_id(app);
+    for screen in &mut app.runtime.screens {
+        for workspace in &mut screen.workspaces {
+            for client in &mut workspace.clients {
+                if client.floating {
+                    client.border = app.config.border_size as u32;
+                }
+                let color = if client.urgent {
+                    app.config.urgent_border_color
+                } else if Some(client.window_id) == current_client {
+                    app.config.active_border_color
+                } else {
+                    app.config.normal_border_color
+                };
+                set_window_border(app.core.display, client.window_id, argb_to_int(color));
+            }
+        }
+    }
+
+    // 4. Arrange
+    arrange_all(app);
+    for screen in 0..app.runtime.screens.len() {
+        for workspace in 0..app.runtime.screens[screen].workspaces.len() {
+            if workspace == app.runtime.screens[screen].current_workspace {
+                show_workspace(app, screen, workspace);
             } else {
-                app.runtime.current_workspace
-            };
-            return ((s, w), 0);
+                hide_workspace(app, screen, workspace);
+            }
         }
     }
 
-    // Use current placement if nothing found;
-    return (default_placement, 0);
+    // 5. Desktops
+    update_desktops(app);
 }
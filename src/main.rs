@@ -6,32 +6,84 @@
 //! - Stack layout
 //! - Shortcuts
 
+pub mod actions;
+pub mod check;
 pub mod config;
 pub mod events;
 pub mod helper;
+pub mod hooks;
+pub mod ipc;
+pub mod layouts;
+pub mod loader;
 pub mod logic;
 pub mod manage;
 pub mod mouse;
+pub mod server;
 pub mod setup;
 pub mod structs;
 pub mod utils;
 pub mod wrapper;
 
 use std::path::Path;
+use std::path::PathBuf;
+use std::process::exit;
 
+use check::check_config;
 use events::*;
 use helper::spawn;
 use libc::LC_CTYPE;
+use logic::feed_window_picker;
+use logic::read_window_picker;
+use logic::reload_config;
+use server::dump_state;
+use server::flush_events;
+use server::handle_ipc;
+use server::ipc_fds;
+use server::ipc_write_fds;
+use server::start_ipc;
+use server::stop_ipc;
 use setup::setup;
 use structs::Application;
+use wrapper::sys::catch_reload_signal;
 use wrapper::sys::no_zombies;
+use wrapper::sys::reload_fd;
 use wrapper::sys::set_locale;
+use wrapper::sys::take_reload_request;
+use wrapper::sys::wait_ready;
+use wrapper::xlib::connection_number;
 use wrapper::xlib::next_event;
+use wrapper::xlib::pending;
 use wrapper::xlib::EEvent;
 
 fn run(app: &mut Application) {
     log!("|===== run =====");
+    let x_fd = connection_number(app.core.display);
     while app.core.running {
+        // Reload config if got SIGHUP
+        if take_reload_request() {
+            reload_config(app);
+        }
+        // Wait for events, SIGHUP wakes up through reload pipe
+        if pending(app.core.display) == 0 {
+            flush_events(app);
+            let picker_stdin = app.runtime.picker.as_ref().and_then(|p| p.stdin);
+            let picker_stdout = app.runtime.picker.as_ref().map(|p| p.stdout);
+            let mut readable = vec![x_fd, reload_fd()];
+            readable.extend(picker_stdout);
+            readable.extend(ipc_fds(app));
+            let mut writable = ipc_write_fds(app);
+            writable.extend(picker_stdin);
+            let ready = wait_ready(&readable, &writable);
+            // Window picker menu takes more entries or has answered
+            if picker_stdin.is_some_and(|fd| ready.contains(&fd)) {
+                feed_window_picker(app);
+            }
+            if picker_stdout.is_some_and(|fd| ready.contains(&fd)) {
+                read_window_picker(app);
+            }
+            handle_ipc(app, &ready);
+            continue;
+        }
         let event = next_event(app.core.display);
         match event {
             EEvent::KeyPress { key } => key_press(app, key),
@@ -60,9 +112,22 @@ fn run(app: &mut Application) {
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(|a| a.as_str()) {
+        Some("--check-config") => exit(check_config(args.get(2).map(PathBuf::from))),
+        Some("--dump-state") => exit(dump_state()),
+        Some(arg) => {
+            eprintln!("Unknown argument `{}`", arg);
+            eprintln!("Usage: rtwm [--check-config [PATH] | --dump-state]");
+            exit(1);
+        }
+        None => {}
+    }
     set_locale(LC_CTYPE, "");
     no_zombies();
+    catch_reload_signal();
     let mut app: Application = setup();
+    start_ipc(&mut app);
     if !Path::new("/tmp/rtwmrunning").exists() {
         for rule in app.config.autostart.clone() {
             spawn(&mut app, &rule.cmd, rule.rule);
@@ -70,4 +135,5 @@ fn main() {
     }
     setup::scan(&mut app);
     run(&mut app);
+    stop_ipc(&mut app);
 }
@@ -4,7 +4,6 @@ use x11::xlib::CWBorderWidth;
 use x11::xlib::EnterWindowMask;
 use x11::xlib::FocusChangeMask;
 use x11::xlib::PropModeAppend;
-use x11::xlib::PropModeReplace;
 use x11::xlib::PropertyChangeMask;
 use x11::xlib::StructureNotifyMask;
 use x11::xlib::SubstructureNotifyMask;
@@ -13,7 +12,9 @@ use x11::xlib::XA_WINDOW;
 
 use crate::config;
 use crate::helper::*;
+use crate::ipc::Event;
 use crate::logic::*;
+use crate::server::emit_event;
 use crate::structs::*;
 use crate::utils::*;
 use crate::wrapper::xlib::*;
@@ -41,6 +42,7 @@ use crate::wrapper::xlib::*;
 /// 14. Configure window
 /// 15. Arrange clients
 /// 16. Map window
+/// 17. Notify subscribers and focus window
 pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     // 1. Get attributes
     let wa;
@@ -55,7 +57,9 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     }
 
     // 2. Check managed
-    if find_window_indexes(app, win).is_some() {
+    if find_window_indexes(app, win).is_some()
+        || app.runtime.scratchpads.iter().any(|c| c.window_id == win)
+    {
         return;
     }
 
@@ -87,21 +91,27 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
             .bar_offsets
             .up as i32;
     c.visible = true;
+    c.factor = 1.0;
 
     println!("{:#?}", c);
 
     // 5. Properties
-    let state = get_atom_prop(app, win, app.atoms.net_wm_state);
+    let state = get_atom_list_prop(app, win, app.atoms.net_wm_state);
     let wtype = get_atom_prop(app, win, app.atoms.net_wm_window_type);
+    (c.instance, c.class) = get_client_class(app, win);
 
     // 10. Get window workspace
-    let ((client_screen, client_workspace), trans) = get_window_placement(app, win, scan);
+    let ((client_screen, mut client_workspace), trans) = get_window_placement(app, win, scan);
+    c.scratchpad = match_scratchpad(app, win);
+    if let Some(name) = &c.scratchpad {
+        app.runtime.pending_scratchpads.retain(|(n, _)| n != name);
+    }
 
     // 6. Update hints
     update_normal_hints(app, &mut c);
 
     // 7. Set flags
-    if state == app.atoms.net_wm_fullscreen {
+    if state.contains(&app.atoms.net_wm_fullscreen) {
         c.floating = true;
         c.fullscreen = true;
     }
@@ -110,14 +120,18 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     }
 
     if !c.floating {
-        c.floating = c.fixed || trans != 0;
+        c.floating = c.fixed || trans != 0 || c.scratchpad.is_some();
+    }
+    c.sticky = state.contains(&app.atoms.net_wm_state_sticky)
+        || find_placement_rule(app, win).is_some_and(|r| r.sticky);
+    if c.sticky {
+        client_workspace = app.runtime.screens[client_screen].current_workspace;
     }
 
-    c.border = if c.floating {
-        app.config.border_size as u32
-    } else {
-        0
-    };
+    // Border of tiled client depends on other tiled clients, so it is set once client is in stack
+    if c.floating {
+        c.border = app.config.border_size as u32;
+    }
 
     // 8. Set input mask for events
     select_input(
@@ -132,6 +146,9 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     }
 
     let screen = &app.runtime.screens[client_screen];
+    if c.scratchpad.is_some() {
+        center_client(&mut c, screen);
+    }
     if c.floating {
         if c.x > screen.width as i32 {
             c.x = c.x % screen.x as i32;
@@ -152,9 +169,14 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     let workspace = &mut app.runtime.screens[client_screen].workspaces[client_workspace];
 
     // 12. Add window to stack
+    let floating = c.floating;
     workspace.current_client = Some(workspace.clients.len());
     app.runtime.current_client = workspace.current_client;
     workspace.clients.push(c);
+    let border = client_border(&app.config, workspace, floating);
+    if let Some(c) = workspace.clients.last_mut() {
+        c.border = border;
+    }
 
     // 13. Update client list & window desktop
     change_property(
@@ -169,6 +191,7 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     );
     let cur_workspace: usize = client_workspace + client_screen * config::NUMBER_OF_DESKTOPS;
     update_client_desktop(app, win, cur_workspace as u64);
+    update_client_state(app, win);
 
     // 14. Configure window
     let mut wc = x11::xlib::XWindowChanges {
@@ -176,23 +199,13 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
         y: 0,
         width: 0,
         height: 0,
-        border_width: app.config.border_size as i32,
+        border_width: border as i32,
         sibling: 0,
         stack_mode: 0,
     };
     configure_window(app.core.display, win, CWBorderWidth as u32, &mut wc);
 
-    let data: [i64; 2] = [1, 0];
-    change_property(
-        app.core.display,
-        win,
-        app.atoms.wm_state,
-        app.atoms.wm_state,
-        32,
-        PropModeReplace,
-        &data as *const [i64; 2] as *mut u8,
-        2,
-    );
+    set_wm_state(app, win, NORMAL_STATE);
 
     // 15. Arrange current workspace
     arrange_workspace(app, client_screen, client_workspace);
@@ -206,6 +219,16 @@ pub fn manage_client(app: &mut Application, win: u64, scan: bool) {
     // 16. Tag window as mapped
     map_window(app.core.display, win);
 
+    // 17. Notify subscribers and focus window
+    emit_event(
+        app,
+        Event::WindowManaged {
+            window: win,
+            screen: client_screen,
+            workspace: client_workspace,
+        },
+    );
+
     if client_screen == app.runtime.current_screen
         && client_workspace == app.runtime.current_workspace
     {
@@ -287,8 +310,10 @@ pub fn unmanage_window(app: &mut Application, win: u64) {
     if let Some((s, w, c)) = find_window_indexes(app, win) {
         // Remove unmapped client
         log!("   |- Found window {} at indexes {}, {}, {}", win, s, w, c);
+        emit_event(app, Event::WindowUnmanaged { window: win });
         // delete_property(app.core.display, win, app.atoms.net_wm_desktop);
         app.runtime.screens[s].workspaces[w].clients.remove(c);
+        app.runtime.minimized.retain(|m| *m != win);
         shift_current_client(app, Some(s), Some(w));
 
         grab_server(app.core.display);
@@ -300,17 +325,7 @@ pub fn unmanage_window(app: &mut Application, win: u64) {
             win,
         );
         println!("===== Set state withdrawn");
-        let data: [i64; 2] = [0, 0];
-        change_property(
-            app.core.display,
-            win,
-            app.atoms.wm_state,
-            app.atoms.wm_state,
-            32,
-            PropModeReplace,
-            &data as *const [i64; 2] as *mut u8,
-            2,
-        );
+        set_wm_state(app, win, WITHDRAWN_STATE);
         println!("===== Ungrab server");
         ungrab_server(app.core.display);
 
@@ -320,6 +335,16 @@ pub fn unmanage_window(app: &mut Application, win: u64) {
             show_workspace(app, s, w);
         }
         update_client_list(app);
+    } else if let Some(index) = app
+        .runtime
+        .scratchpads
+        .iter()
+        .position(|c| c.window_id == win)
+    {
+        // Remove hidden scratchpad
+        app.runtime.scratchpads.remove(index);
+        emit_event(app, Event::WindowUnmanaged { window: win });
+        update_client_list(app);
     } else {
         if app
             .runtime
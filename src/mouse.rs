@@ -38,7 +38,7 @@ pub fn move_mouse(app: &mut Application, motion_event: XMotionEvent) {
             // Update workspace
             let new_workspace: usize = app.runtime.screens[new_screen].current_workspace
                 + new_screen * crate::config::NUMBER_OF_DESKTOPS;
-            update_client_desktop(app, client.window_id, new_workspace as u64);
+            let win = client.window_id;
 
             change_property(
                 app.core.display,
@@ -59,6 +59,7 @@ pub fn move_mouse(app: &mut Application, motion_event: XMotionEvent) {
             app.runtime.screens[new_screen].workspaces[nw]
                 .clients
                 .push(client);
+            update_client_desktop(app, win, new_workspace as u64);
             s = new_screen;
             app.runtime.current_screen = s;
 
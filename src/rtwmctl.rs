@@ -0,0 +1,232 @@
+//! Command line client of rtwm IPC socket
+//!
+//! Exit codes:
+//! - 0 - request succeeded
+//! - 1 - window manager refused request
+//! - 2 - invalid arguments
+//! - 3 - window manager is not reachable
+
+pub mod actions;
+pub mod ipc;
+
+use std::ffi::CString;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::process::exit;
+use std::str::FromStr;
+
+use actions::*;
+use ipc::*;
+
+const USAGE: &str = "Usage: rtwmctl <command> [args]
+
+Commands:
+  get tree                      Print screens, workspaces and windows as JSON
+  subscribe [kinds]             Print events as JSON lines until window manager exits,
+                                kinds are comma separated list of workspace, window,
+                                focus, title, urgent, layout and screen (default all)
+  workspace <n>                 Focus workspace n of current screen (from 1)
+  move-to-workspace <n>         Move focused window to workspace n (from 1)
+  focus-on-screen next|previous Focus next/previous screen
+  move-to-screen next|previous  Move focused window to next/previous screen
+  spawn <cmd> [args]            Run program
+  run-or-raise <class> <cmd>    Focus window of class or run program
+  kill-client                   Close focused window
+  toggle-float                  Toggle floating of focused window
+  toggle-sticky                 Toggle sticky of focused window
+  minimize                      Minimize focused window
+  restore-last                  Restore last minimized window
+  scratchpad <name>             Toggle scratchpad
+  pick-window [pull]            Choose window with menu
+  cycle-stack <i>               Focus window i positions away
+  move-in-stack <i>             Move focused window i positions away
+  zoom                          Swap focused window with master
+  focus-direction <dir>         Focus window to the left/right/up/down
+  swap-direction <dir>          Swap focused window with one to the left/right/up/down
+  master-capacity <i>           Change number of master windows by i
+  master-width <f>              Change master width by f
+  client-factor <f>             Change size factor of focused window by f
+  gaps <i>                      Change gaps of current workspace by i
+  toggle-gaps                   Toggle gaps of current workspace
+  layout <name>                 Set layout of current workspace
+  cycle-layout                  Switch to next layout
+  dump-info                     Print runtime info in window manager log
+  reload-config                 Reload config
+  quit                          Quit window manager";
+
+const EXIT_REFUSED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_UNREACHABLE: i32 = 3;
+
+/// Parse argument at position
+fn arg<T: FromStr>(args: &[String], index: usize) -> Result<T, String> {
+    let value = args
+        .get(index)
+        .ok_or_else(|| format!("`{}` needs more arguments", args[0]))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid argument `{}` of `{}`", value, args[0]))
+}
+
+/// Parse workspace counted from 1
+fn workspace(args: &[String]) -> Result<u64, String> {
+    match arg::<u64>(args, 1)? {
+        0 => Err("workspaces are counted from 1".to_string()),
+        n => Ok(n - 1),
+    }
+}
+
+fn screen_switching(args: &[String]) -> Result<ScreenSwitching, String> {
+    match arg::<String>(args, 1)?.as_str() {
+        "next" => Ok(ScreenSwitching::Next),
+        "previous" | "prev" => Ok(ScreenSwitching::Previous),
+        d => Err(format!("unknown screen direction `{}`", d)),
+    }
+}
+
+fn direction(args: &[String]) -> Result<Direction, String> {
+    match arg::<String>(args, 1)?.as_str() {
+        "left" => Ok(Direction::Left),
+        "right" => Ok(Direction::Right),
+        "up" => Ok(Direction::Up),
+        "down" => Ok(Direction::Down),
+        d => Err(format!("unknown direction `{}`", d)),
+    }
+}
+
+fn command(args: &[String]) -> Result<Vec<CString>, String> {
+    if args.is_empty() {
+        return Err("command is empty".to_string());
+    }
+    args.iter()
+        .map(|a| CString::new(a.as_str()).map_err(|_| format!("invalid argument `{}`", a)))
+        .collect()
+}
+
+/// Parse comma separated event kinds, no argument means all events
+fn event_kinds(args: &[String]) -> Result<Vec<EventKind>, String> {
+    match args.get(1) {
+        Some(kinds) => kinds
+            .split(',')
+            .map(|kind| {
+                serde_json::from_value(serde_json::Value::from(kind))
+                    .map_err(|_| format!("unknown event kind `{}`", kind))
+            })
+            .collect(),
+        None => Ok(vec![]),
+    }
+}
+
+/// Print events until window manager closes connection
+fn print_events(events: BufReader<UnixStream>) -> i32 {
+    let mut stdout = std::io::stdout();
+    for line in events.lines() {
+        match line {
+            Ok(line) => {
+                if writeln!(stdout, "{}", line)
+                    .and_then(|_| stdout.flush())
+                    .is_err()
+                {
+                    // Reader of output has exited
+                    return 0;
+                }
+            }
+            Err(e) => {
+                eprintln!("rtwmctl: {}", e);
+                return EXIT_UNREACHABLE;
+            }
+        }
+    }
+    0
+}
+
+/// Convert command line arguments to request
+fn parse_request(args: &[String]) -> Result<Request, String> {
+    let action = match args[0].as_str() {
+        "get" => match arg::<String>(args, 1)?.as_str() {
+            "tree" => return Ok(Request::Get(Query::Tree)),
+            q => return Err(format!("unknown query `{}`", q)),
+        },
+        "subscribe" => return Ok(Request::Subscribe(event_kinds(args)?)),
+        "workspace" => ActionResult::FocusOnWorkspace(workspace(args)?),
+        "move-to-workspace" => ActionResult::MoveToWorkspace(workspace(args)?),
+        "focus-on-screen" => ActionResult::FocusOnScreen(screen_switching(args)?),
+        "move-to-screen" => ActionResult::MoveToScreen(screen_switching(args)?),
+        "spawn" => ActionResult::Spawn(command(&args[1..])?),
+        "run-or-raise" => ActionResult::RunOrRaise {
+            class: arg(args, 1)?,
+            cmd: command(args.get(2..).unwrap_or_default())?,
+        },
+        "kill-client" => ActionResult::KillClient,
+        "toggle-float" => ActionResult::ToggleFloat,
+        "toggle-sticky" => ActionResult::ToggleSticky,
+        "minimize" => ActionResult::Minimize,
+        "restore-last" => ActionResult::RestoreLast,
+        "scratchpad" => ActionResult::ToggleScratchpad(arg(args, 1)?),
+        "pick-window" => match args.get(1).map(|a| a.as_str()) {
+            None => ActionResult::PickWindow(false),
+            Some("pull") => ActionResult::PickWindow(true),
+            Some(a) => return Err(format!("invalid argument `{}` of `{}`", a, args[0])),
+        },
+        "cycle-stack" => ActionResult::CycleStack(arg(args, 1)?),
+        "move-in-stack" => ActionResult::MoveInStack(arg(args, 1)?),
+        "zoom" => ActionResult::Zoom,
+        "focus-direction" => ActionResult::FocusDirection(direction(args)?),
+        "swap-direction" => ActionResult::SwapDirection(direction(args)?),
+        "master-capacity" => ActionResult::UpdateMasterCapacity(arg(args, 1)?),
+        "master-width" => ActionResult::UpdateMasterWidth(arg(args, 1)?),
+        "client-factor" => ActionResult::UpdateClientFactor(arg(args, 1)?),
+        "gaps" => ActionResult::UpdateGaps(arg(args, 1)?),
+        "toggle-gaps" => ActionResult::ToggleGaps,
+        "layout" => ActionResult::SetLayout(arg(args, 1)?),
+        "cycle-layout" => ActionResult::CycleLayout,
+        "dump-info" => ActionResult::DumpInfo,
+        "reload-config" => ActionResult::ReloadConfig,
+        "quit" => ActionResult::Quit,
+        c => return Err(format!("unknown command `{}`", c)),
+    };
+    Ok(Request::Action(action))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        println!("{}", USAGE);
+        exit(if args.is_empty() { EXIT_USAGE } else { 0 });
+    }
+    let request = match parse_request(&args) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("rtwmctl: {}", e);
+            eprintln!("Run `rtwmctl --help` for list of commands");
+            exit(EXIT_USAGE);
+        }
+    };
+    match open_request(&request) {
+        Ok((Reply::Ok, events)) => {
+            if let Request::Subscribe(_) = request {
+                exit(print_events(events));
+            }
+        }
+        Ok((Reply::Tree(tree), _)) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&tree).unwrap_or_default()
+            );
+        }
+        Ok((Reply::Error(e), _)) => {
+            eprintln!("rtwmctl: {}", e);
+            exit(EXIT_REFUSED);
+        }
+        Err(e) => {
+            eprintln!(
+                "rtwmctl: failed to talk to rtwm on `{}`: {}",
+                socket_path().display(),
+                e
+            );
+            exit(EXIT_UNREACHABLE);
+        }
+    }
+}
@@ -0,0 +1,481 @@
+//! IPC socket serving requests from rtwmctl and other programs
+
+use std::fs::DirBuilder;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+
+use nix::sys::socket::getsockopt;
+use nix::sys::socket::sockopt::PeerCredentials;
+use nix::sys::stat::Mode;
+use nix::unistd::getuid;
+
+use crate::config::NUMBER_OF_DESKTOPS;
+use crate::events::run_action;
+use crate::helper::get_current_client_id;
+use crate::hooks::run_event_hooks;
+use crate::ipc::*;
+use crate::layouts::layout_index;
+use crate::layouts::LAYOUTS;
+use crate::log;
+use crate::structs::*;
+
+/// Longest accepted request line, longer ones close connection
+const MAX_REQUEST: usize = 64 * 1024;
+/// Most output queued for client not reading it, connection is closed when exceeded
+const MAX_OUTPUT: usize = 4 * 1024 * 1024;
+
+/// Create IPC socket
+/// 1. Make sure socket lives in private directory
+/// 2. Check if other window manager is already listening on socket
+/// 3. Remove stale socket left by crashed instance
+/// 4. Bind and listen without blocking, only owner may connect
+pub fn start_ipc(app: &mut Application) {
+    let path = socket_path();
+    log!("|- Starting IPC on `{}`", path.display());
+    // 1. Make sure socket lives in private directory
+    if let Some(dir) = path.parent() {
+        let _ = DirBuilder::new().mode(0o700).create(dir);
+    }
+    if let Err(e) = check_socket_dir(&path) {
+        eprintln!("rtwm: refusing to start IPC: {}", e);
+        return;
+    }
+    // 2. Check if other window manager is already listening on socket
+    if UnixStream::connect(&path).is_ok() {
+        eprintln!("rtwm: IPC socket `{}` is already in use", path.display());
+        return;
+    }
+    // 3. Remove stale socket left by crashed instance
+    let _ = std::fs::remove_file(&path);
+    // 4. Bind and listen without blocking, only owner may connect.
+    // Socket accepts `spawn`, so it is created with 0600 mode right away
+    let umask = nix::sys::stat::umask(Mode::from_bits_truncate(0o177));
+    let listener = UnixListener::bind(&path);
+    nix::sys::stat::umask(umask);
+    let listener = match listener {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("rtwm: failed to bind `{}`: {}", path.display(), e);
+            return;
+        }
+    };
+    if let Err(e) = listener.set_nonblocking(true) {
+        eprintln!("rtwm: failed to set up IPC socket: {}", e);
+        return;
+    }
+    app.runtime.ipc = Some(IpcServer {
+        listener,
+        path,
+        clients: vec![],
+        events: vec![],
+        focused: None,
+    });
+}
+
+/// Remove IPC socket on exit
+pub fn stop_ipc(app: &mut Application) {
+    if let Some(ipc) = app.runtime.ipc.take() {
+        let _ = std::fs::remove_file(ipc.path);
+    }
+}
+
+/// File descriptors of IPC socket and its clients to wait on
+pub fn ipc_fds(app: &Application) -> Vec<i32> {
+    match &app.runtime.ipc {
+        Some(ipc) => std::iter::once(ipc.listener.as_raw_fd())
+            .chain(ipc.clients.iter().map(|c| c.stream.as_raw_fd()))
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// File descriptors of IPC clients with queued output
+pub fn ipc_write_fds(app: &Application) -> Vec<i32> {
+    match &app.runtime.ipc {
+        Some(ipc) => ipc
+            .clients
+            .iter()
+            .filter(|c| !c.output.is_empty())
+            .map(|c| c.stream.as_raw_fd())
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Check that process on other end of connection runs as same user as window manager
+fn peer_is_owner(stream: &UnixStream) -> bool {
+    getsockopt(stream.as_raw_fd(), PeerCredentials)
+        .is_ok_and(|credentials| credentials.uid() == getuid().as_raw())
+}
+
+/// Queue data for client and write as much of it as client accepts
+///
+/// Returns false if connection should be closed
+fn send(client: &mut IpcClient, data: &[u8]) -> bool {
+    client.output.extend_from_slice(data);
+    flush_client(client)
+}
+
+/// Write queued output until client stops accepting it
+///
+/// Returns false if connection is broken or client fell too far behind
+fn flush_client(client: &mut IpcClient) -> bool {
+    while !client.output.is_empty() {
+        match client.stream.write(&client.output) {
+            Ok(0) => return false,
+            Ok(n) => {
+                client.output.drain(..n);
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => return false,
+        }
+    }
+    client.output.len() <= MAX_OUTPUT
+}
+
+/// Serve IPC file descriptors that became ready
+/// 1. Accept new connections
+/// 2. Read from ready clients, drop closed ones and ones sending too long requests
+/// 3. Queue reply to every complete request line
+/// 4. Write queued output
+pub fn handle_ipc(app: &mut Application, ready: &[i32]) {
+    let mut clients = match &mut app.runtime.ipc {
+        Some(ipc) => {
+            // 1. Accept new connections
+            if ready.contains(&ipc.listener.as_raw_fd()) {
+                loop {
+                    match ipc.listener.accept() {
+                        Ok((stream, _)) if !peer_is_owner(&stream) => {
+                            eprintln!("rtwm: refused IPC connection of other user");
+                        }
+                        Ok((stream, _)) => {
+                            // Slow clients must never block window manager
+                            if let Err(e) = stream.set_nonblocking(true) {
+                                eprintln!("rtwm: failed to set up IPC connection: {}", e);
+                                continue;
+                            }
+                            ipc.clients.push(IpcClient {
+                                stream,
+                                buffer: vec![],
+                                output: vec![],
+                                subscriptions: None,
+                            });
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                        Err(e) => {
+                            eprintln!("rtwm: failed to accept IPC connection: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+            // Take clients since requests need whole application
+            std::mem::take(&mut ipc.clients)
+        }
+        None => return,
+    };
+    clients.retain_mut(|client| {
+        if !ready.contains(&client.stream.as_raw_fd()) {
+            return true;
+        }
+        // 2. Read from ready clients, drop closed ones and ones sending too long requests
+        let mut buffer = [0u8; 4096];
+        match client.stream.read(&mut buffer) {
+            Ok(0) => return false,
+            Ok(n) => client.buffer.extend_from_slice(&buffer[..n]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) => {}
+            Err(_) => return false,
+        }
+        // 3. Queue reply to every complete request line
+        while let Some(end) = client.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = client.buffer.drain(..=end).collect();
+            let reply = handle_request(app, client, &String::from_utf8_lossy(&line));
+            let mut data = serde_json::to_vec(&reply).unwrap_or_default();
+            data.push(b'\n');
+            client.output.extend_from_slice(&data);
+        }
+        if client.buffer.len() > MAX_REQUEST {
+            eprintln!("rtwm: IPC request is too long, closing connection");
+            return false;
+        }
+        // 4. Write queued output
+        flush_client(client)
+    });
+    if let Some(ipc) = &mut app.runtime.ipc {
+        ipc.clients = clients;
+    }
+}
+
+/// Print state of running window manager as JSON
+///
+/// Returns exit code: 0 if state was printed, 1 otherwise
+pub fn dump_state() -> i32 {
+    match send_request(&Request::Get(Query::Tree)) {
+        Ok(Reply::Tree(tree)) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&tree).unwrap_or_default()
+            );
+            0
+        }
+        Ok(Reply::Error(e)) => {
+            eprintln!("rtwm: {}", e);
+            1
+        }
+        Ok(reply) => {
+            eprintln!("rtwm: unexpected reply {:?}", reply);
+            1
+        }
+        Err(e) => {
+            eprintln!(
+                "rtwm: failed to talk to rtwm on `{}`: {}",
+                socket_path().display(),
+                e
+            );
+            1
+        }
+    }
+}
+
+/// Parse request line and run it
+fn handle_request(app: &mut Application, client: &mut IpcClient, line: &str) -> Reply {
+    log!("|- Got IPC request `{}`", line.trim());
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Reply::Error(format!("invalid request: {}", e)),
+    };
+    match request {
+        Request::Action(action) => match check_action(app, &action) {
+            Ok(()) => {
+                run_action(app, &action);
+                Reply::Ok
+            }
+            Err(e) => Reply::Error(e),
+        },
+        Request::Get(Query::Tree) => Reply::Tree(tree(app)),
+        Request::Subscribe(kinds) => {
+            client.subscriptions = Some(kinds);
+            Reply::Ok
+        }
+    }
+}
+
+fn client_info(client: &Client) -> ClientInfo {
+    ClientInfo {
+        window: client.window_id,
+        title: client.window_name.clone(),
+        instance: client.instance.clone(),
+        class: client.class.clone(),
+        x: client.x,
+        y: client.y,
+        width: client.w,
+        height: client.h,
+        floating: client.floating,
+        fullscreen: client.fullscreen,
+        urgent: client.urgent,
+        sticky: client.sticky,
+        minimized: client.minimized,
+        scratchpad: client.scratchpad.clone(),
+    }
+}
+
+/// Build exported state from runtime
+fn tree(app: &mut Application) -> Tree {
+    let config = &app.config;
+    let runtime = &app.runtime;
+    let screens = runtime
+        .screens
+        .iter()
+        .enumerate()
+        .map(|(s, screen)| ScreenInfo {
+            index: s,
+            x: screen.x,
+            y: screen.y,
+            width: screen.width,
+            height: screen.height,
+            bar_offsets: OffsetsInfo {
+                left: screen.bar_offsets.left,
+                up: screen.bar_offsets.up,
+                right: screen.bar_offsets.right,
+                down: screen.bar_offsets.down,
+            },
+            current_workspace: screen.current_workspace,
+            workspaces: screen
+                .workspaces
+                .iter()
+                .enumerate()
+                .map(|(w, workspace)| WorkspaceInfo {
+                    index: w,
+                    name: config
+                        .desktops
+                        .names
+                        .get(s)
+                        .map_or_else(|| (w + 1).to_string(), |names| names[w].clone()),
+                    layout: LAYOUTS[workspace.layout].name().to_string(),
+                    master_capacity: workspace.master_capacity,
+                    master_width: workspace.master_width,
+                    inner_gap: workspace.inner_gap.unwrap_or(config.inner_gap_width),
+                    outer_gap: workspace.outer_gap.unwrap_or(config.outer_gap_width),
+                    gaps_enabled: workspace.gaps_enabled,
+                    focused_window: workspace
+                        .current_client
+                        .and_then(|c| workspace.clients.get(c))
+                        .map(|c| c.window_id),
+                    clients: workspace.clients.iter().map(client_info).collect(),
+                })
+                .collect(),
+        })
+        .collect();
+    Tree {
+        screens,
+        focused_screen: runtime.current_screen,
+        focused_window: get_current_client_id(app),
+        bars: app
+            .runtime
+            .bars
+            .iter()
+            .map(|bar| BarInfo {
+                window: bar.window_id,
+                x: bar.x,
+                y: bar.y,
+                width: bar.w,
+                height: bar.h,
+            })
+            .collect(),
+        scratchpads: app.runtime.scratchpads.iter().map(client_info).collect(),
+        minimized: app.runtime.minimized.iter().rev().copied().collect(),
+    }
+}
+
+fn event_kind(event: &Event) -> EventKind {
+    match event {
+        Event::Workspace { .. } => EventKind::Workspace,
+        Event::WindowManaged { .. } | Event::WindowUnmanaged { .. } => EventKind::Window,
+        Event::Focus { .. } => EventKind::Focus,
+        Event::Title { .. } => EventKind::Title,
+        Event::Urgent { .. } => EventKind::Urgent,
+        Event::Layout { .. } => EventKind::Layout,
+        Event::Screens { .. } => EventKind::Screen,
+    }
+}
+
+/// Queue event for subscribers, it is sent before waiting for next X event
+///
+/// Events are queued even if clients are taken out while handling requests,
+/// they are matched against subscriptions when sent. Also runs hooks
+/// configured for event
+pub fn emit_event(app: &mut Application, event: Event) {
+    run_event_hooks(app, &event);
+    if let Some(ipc) = &mut app.runtime.ipc {
+        ipc.events.push(event);
+    }
+}
+
+/// Queue focus event if focused window differs from last sent one
+pub fn emit_focus(app: &mut Application, window: Option<u64>) {
+    if let Some(ipc) = &mut app.runtime.ipc {
+        if ipc.focused != window {
+            ipc.focused = window;
+            emit_event(app, Event::Focus { window });
+        }
+    }
+}
+
+/// Queue layout event for current workspace
+pub fn emit_layout(app: &mut Application) {
+    let (screen, workspace) = (app.runtime.current_screen, app.runtime.current_workspace);
+    let ws = &app.runtime.screens[screen].workspaces[workspace];
+    let event = Event::Layout {
+        screen,
+        workspace,
+        layout: LAYOUTS[ws.layout].name().to_string(),
+        master_capacity: ws.master_capacity,
+        master_width: ws.master_width,
+    };
+    emit_event(app, event);
+}
+
+/// Send queued events to subscribers
+/// 1. Serialize every event once
+/// 2. Queue events client is subscribed to, drop client if it falls too far behind
+pub fn flush_events(app: &mut Application) {
+    let ipc = match &mut app.runtime.ipc {
+        Some(ipc) if !ipc.events.is_empty() => ipc,
+        _ => return,
+    };
+    if !ipc.clients.iter().any(|c| c.subscriptions.is_some()) {
+        ipc.events.clear();
+        return;
+    }
+    // 1. Serialize every event once
+    let events: Vec<(EventKind, Vec<u8>)> = ipc
+        .events
+        .drain(..)
+        .filter_map(|event| {
+            let mut line = serde_json::to_vec(&event).ok()?;
+            line.push(b'\n');
+            Some((event_kind(&event), line))
+        })
+        .collect();
+    // 2. Queue events client is subscribed to, drop client if it falls too far behind
+    ipc.clients.retain_mut(|client| {
+        let subscriptions = match &client.subscriptions {
+            Some(subscriptions) => subscriptions,
+            None => return true,
+        };
+        let data: Vec<u8> = events
+            .iter()
+            .filter(|(kind, _)| subscriptions.is_empty() || subscriptions.contains(kind))
+            .flat_map(|(_, line)| line.iter().copied())
+            .collect();
+        send(client, &data)
+    });
+}
+
+/// Check action arguments which are trusted when coming from config
+fn check_action(app: &mut Application, action: &ActionResult) -> Result<(), String> {
+    match action {
+        ActionResult::Spawn(cmd) | ActionResult::RunOrRaise { cmd, .. } if cmd.is_empty() => {
+            return Err("command is empty".to_string());
+        }
+        ActionResult::MoveToWorkspace(n) | ActionResult::FocusOnWorkspace(n)
+            if *n as usize >= NUMBER_OF_DESKTOPS =>
+        {
+            return Err(format!(
+                "workspace {} is out of range, there are {} workspaces",
+                n, NUMBER_OF_DESKTOPS
+            ));
+        }
+        ActionResult::SetLayout(name) if layout_index(name).is_none() => {
+            return Err(format!("unknown layout `{}`", name));
+        }
+        ActionResult::ToggleScratchpad(name)
+            if !app.config.scratchpads.iter().any(|s| &s.name == name) =>
+        {
+            return Err(format!("unknown scratchpad `{}`", name));
+        }
+        _ => {}
+    }
+    let needs_client = matches!(
+        action,
+        ActionResult::KillClient
+            | ActionResult::ToggleFloat
+            | ActionResult::ToggleSticky
+            | ActionResult::Minimize
+            | ActionResult::MoveToWorkspace(_)
+            | ActionResult::MoveToScreen(_)
+            | ActionResult::UpdateClientFactor(_)
+            | ActionResult::MoveInStack(_)
+            | ActionResult::SwapDirection(_)
+    );
+    if needs_client && get_current_client_id(app).is_none() {
+        return Err("no focused window".to_string());
+    }
+    Ok(())
+}
@@ -1,6 +1,6 @@
 //! Code for setting up WM. Intented to be ran once
 
-use crate::config::*;
+use crate::loader::load_config;
 use crate::manage::*;
 use crate::structs::*;
 use crate::utils::*;
@@ -35,6 +35,7 @@ use x11::xlib::Display;
 /// #### Sequence of actions done in setup:
 /// 1. Open [`Display`] connection & finds root window
 /// 2. Create empty [`Application`] struct
+///     * Call [`load_config`]
 /// 3. Init atoms.
 ///     * Call [`init_supported_atoms`]
 /// 4. Create helper window
@@ -62,7 +63,7 @@ pub fn setup() -> Application {
 
     // 2. Create struct
     let mut app = Application {
-        config: config(),
+        config: load_config(),
         core: WmCore {
             display,
             root_win,
@@ -81,12 +82,18 @@ pub fn setup() -> Application {
             current_client: None,
             bars: vec![],
             autostart_rules: vec![],
+            scratchpads: vec![],
+            pending_scratchpads: vec![],
+            minimized: vec![],
+            picker: None,
+            ipc: None,
         },
         atoms: Atoms {
             utf8string: 0,
             wm_protocols: 0,
             wm_delete: 0,
             wm_state: 0,
+            wm_change_state: 0,
             wm_name: 0,
             net_wm_check: 0,
             wm_take_focus: 0,
@@ -96,6 +103,8 @@ pub fn setup() -> Application {
             net_wm_state: 0,
             net_wm_state_demands_attention: 0,
             net_wm_fullscreen: 0,
+            net_wm_state_sticky: 0,
+            net_wm_state_hidden: 0,
             net_wm_window_type: 0,
             net_wm_window_type_dock: 0,
             net_wm_window_type_dialog: 0,
@@ -106,6 +115,7 @@ pub fn setup() -> Application {
             net_desktop_names: 0,
             net_wm_desktop: 0,
             net_wm_pid: 0,
+            rtwm_desktop_layouts: 0,
         },
     };
 
@@ -228,6 +238,7 @@ pub fn init_supported_atoms(app: &mut Application) {
         wm_protocols: intern_atom!("WM_PROTOCOLS"),
         wm_delete: intern_atom!("WM_DELETE_WINDOW"),
         wm_state: intern_atom!("WM_STATE"),
+        wm_change_state: intern_atom!("WM_CHANGE_STATE"),
         wm_name: intern_atom!("WM_NAME"),
         wm_take_focus: intern_atom!("WM_TAKE_FOCUS"),
         net_active_window: intern_atom!("_NET_ACTIVE_WINDOW"),
@@ -237,6 +248,8 @@ pub fn init_supported_atoms(app: &mut Application) {
         net_wm_state_demands_attention: intern_atom!("_NET_WM_STATE_DEMANDS_ATTENTION"),
         net_wm_check: intern_atom!("_NET_SUPPORTING_WM_CHECK"),
         net_wm_fullscreen: intern_atom!("_NET_WM_STATE_FULLSCREEN"),
+        net_wm_state_sticky: intern_atom!("_NET_WM_STATE_STICKY"),
+        net_wm_state_hidden: intern_atom!("_NET_WM_STATE_HIDDEN"),
         net_wm_window_type: intern_atom!("_NET_WM_WINDOW_TYPE"),
         net_wm_window_type_dialog: intern_atom!("_NET_WM_WINDOW_TYPE_DIALOG"),
         net_wm_window_type_dock: intern_atom!("_NET_WM_WINDOW_TYPE_DOCK"),
@@ -247,6 +260,7 @@ pub fn init_supported_atoms(app: &mut Application) {
         net_desktop_viewport: intern_atom!("_NET_DESKTOP_VIEWPORT"),
         net_wm_desktop: intern_atom!("_NET_WM_DESKTOP"),
         net_wm_pid: intern_atom!("_NET_WM_PID"),
+        rtwm_desktop_layouts: intern_atom!("_RTWM_DESKTOP_LAYOUTS"),
     };
     let mut netatoms = vec![
         app.atoms.net_active_window,
@@ -254,6 +268,8 @@ pub fn init_supported_atoms(app: &mut Application) {
         app.atoms.net_wm_name,
         app.atoms.net_wm_check,
         app.atoms.net_wm_fullscreen,
+        app.atoms.net_wm_state_sticky,
+        app.atoms.net_wm_state_hidden,
         app.atoms.net_wm_window_type,
         app.atoms.net_wm_window_type_dialog,
         app.atoms.net_client_list,
@@ -1,8 +1,17 @@
 //! All newly defined structs used by window manager
 
 use std::ffi::CString;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Instant;
 
+use serde::Deserialize;
+
+pub use crate::actions::*;
 use crate::config::NUMBER_OF_DESKTOPS;
+use crate::ipc::Event;
+use crate::ipc::EventKind;
 
 pub struct Application {
     pub config: Configuration,
@@ -11,64 +20,110 @@ pub struct Application {
     pub atoms: Atoms,
 }
 
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Configuration {
     pub key_actions: Vec<KeyAction>,
-    pub gap_width: usize,
+    pub inner_gap_width: usize,
+    pub outer_gap_width: usize,
     pub border_size: usize,
     pub normal_border_color: Color,
     pub active_border_color: Color,
     pub urgent_border_color: Color,
+    pub warp_pointer: bool,
+    /// Drop gaps when only one tiled client is visible
+    pub smart_gaps: bool,
+    /// Drop borders when only one tiled client is visible
+    pub smart_borders: bool,
+    /// Menu program used by window picker, reads lines from stdin and prints chosen one
+    pub window_picker: Vec<CString>,
     pub desktops: DesktopsConfig,
     pub autostart: Vec<AutostartRuleCMD>,
     pub placements: Vec<PlacementRule>,
+    pub scratchpads: Vec<ScratchpadRule>,
+    pub hooks: Vec<HookRule>,
 }
 
-#[derive(Debug, Clone)]
+impl Default for Configuration {
+    /// Compiled-in configuration from `config.rs`
+    fn default() -> Self {
+        crate::config::config()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct AutostartRuleCMD {
     pub cmd: Vec<CString>,
+    #[serde(default)]
     pub rule: Option<(usize, usize)>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PlacementRule {
+    #[serde(default)]
     pub instance: Option<String>,
+    #[serde(default)]
     pub class: Option<String>,
+    #[serde(default)]
     pub title: Option<String>,
+    #[serde(default)]
     pub rule_screen: Option<usize>,
+    #[serde(default)]
     pub rule_workspace: Option<usize>,
+    #[serde(default)]
+    pub sticky: bool,
 }
 
-#[derive(Clone)]
+/// Named floating window toggled on focused screen
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScratchpadRule {
+    pub name: String,
+    pub cmd: Vec<CString>,
+    #[serde(default)]
+    pub instance: Option<String>,
+    #[serde(default)]
+    pub class: Option<String>,
+}
+
+/// Command spawned on window manager event
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookRule {
+    pub event: HookEvent,
+    pub cmd: Vec<CString>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    WorkspaceChanged,
+    ClientManaged,
+    ClientUrgent,
+    ScreenAdded,
+    ScreenRemoved,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "KeyActionDef")]
 pub struct KeyAction {
     pub keysym: u32,
     pub modifier: u32,
     pub result: ActionResult,
 }
 
-#[derive(Debug, Clone)]
-pub enum ActionResult {
-    KillClient,
-    Spawn(Vec<CString>),
-    MoveToScreen(ScreenSwitching),
-    FocusOnScreen(ScreenSwitching),
-    UpdateMasterCapacity(i64),
-    UpdateMasterWidth(f64),
-    MoveToWorkspace(u64),
-    FocusOnWorkspace(u64),
-    CycleStack(i64),
-    PopPushStack,
-    ToggleFloat,
-    DumpInfo,
-    Quit,
-}
-
-#[derive(Debug, Clone, Copy)]
-pub enum ScreenSwitching {
-    Next,
-    Previous,
-}
-
-#[derive(Clone, Copy)]
+/// Key action as written in config file, e.g. `{ key = "Mod4+Shift+q", result = "quit" }`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyActionDef {
+    pub key: String,
+    pub result: ActionResult,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
 pub struct Color {
     pub alpha: u8,
     pub red: u8,
@@ -76,7 +131,10 @@ pub struct Color {
     pub blue: u8,
 }
 
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct DesktopsConfig {
+    #[serde(deserialize_with = "crate::loader::deserialize_keysyms")]
     pub keysyms: [u32; NUMBER_OF_DESKTOPS],
     pub names: Vec<[String; NUMBER_OF_DESKTOPS]>,
 }
@@ -90,12 +148,19 @@ impl DesktopsConfig {
     }
 }
 
+impl Default for DesktopsConfig {
+    fn default() -> Self {
+        DesktopsConfig::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct Atoms {
     pub utf8string: u64,
     pub wm_protocols: u64,
     pub wm_delete: u64,
     pub wm_state: u64,
+    pub wm_change_state: u64,
     pub wm_take_focus: u64,
     pub wm_name: u64,
     pub net_active_window: u64,
@@ -105,6 +170,8 @@ pub struct Atoms {
     pub net_wm_state_demands_attention: u64,
     pub net_wm_check: u64,
     pub net_wm_fullscreen: u64,
+    pub net_wm_state_sticky: u64,
+    pub net_wm_state_hidden: u64,
     pub net_wm_window_type: u64,
     pub net_wm_window_type_dialog: u64,
     pub net_wm_window_type_dock: u64,
@@ -115,6 +182,7 @@ pub struct Atoms {
     pub net_desktop_names: u64,
     pub net_desktop_viewport: u64,
     pub net_wm_pid: u64,
+    pub rtwm_desktop_layouts: u64,
 }
 
 pub struct WmCore {
@@ -144,6 +212,57 @@ pub struct Runtime {
     pub mouse_state: MouseState, // win, button, pos
     pub bars: Vec<Bar>, // Not in screens since logically bars are not limited to specific screen
     pub autostart_rules: Vec<AutostartRulePID>,
+    /// Hidden scratchpad clients, shown ones live in workspaces
+    pub scratchpads: Vec<Client>,
+    /// Scratchpads spawned but without mapped window yet, with spawn time
+    pub pending_scratchpads: Vec<(String, Instant)>,
+    /// Minimized windows, last minimized is on top
+    pub minimized: Vec<u64>,
+    /// Running window picker menu
+    pub picker: Option<WindowPicker>,
+    /// Socket accepting commands, `None` if it could not be created
+    pub ipc: Option<IpcServer>,
+}
+
+/// Listening IPC socket and connected clients
+#[derive(Debug)]
+pub struct IpcServer {
+    pub listener: UnixListener,
+    pub path: PathBuf,
+    pub clients: Vec<IpcClient>,
+    /// Events waiting to be sent to subscribers
+    pub events: Vec<Event>,
+    /// Last focused window sent to subscribers
+    pub focused: Option<u64>,
+}
+
+/// Connection to IPC socket, `buffer` holds incomplete request line
+#[derive(Debug)]
+pub struct IpcClient {
+    pub stream: UnixStream,
+    pub buffer: Vec<u8>,
+    /// Replies and events not yet written since client is not reading
+    pub output: Vec<u8>,
+    /// Kinds of events client is subscribed to, empty if all
+    pub subscriptions: Option<Vec<EventKind>>,
+}
+
+/// Menu started by window picker
+///
+/// Entries are written to `stdin` and choice is read from `stdout` when they are ready,
+/// so slow menu never blocks window manager
+#[derive(Debug)]
+pub struct WindowPicker {
+    /// `None` once all entries are written
+    pub stdin: Option<i32>,
+    /// Entries not yet written to menu
+    pub input: Vec<u8>,
+    pub stdout: i32,
+    /// Output of menu read so far
+    pub output: Vec<u8>,
+    /// Windows shown in menu
+    pub entries: Vec<u64>,
+    pub pull: bool,
 }
 
 #[derive(Debug)]
@@ -170,6 +289,11 @@ pub struct Workspace {
     pub number: u64,
     pub master_capacity: i64,
     pub master_width: f64,
+    pub layout: usize,
+    /// Overrides for configured gaps
+    pub inner_gap: Option<usize>,
+    pub outer_gap: Option<usize>,
+    pub gaps_enabled: bool,
     pub clients: Vec<Client>,
     pub current_client: Option<usize>,
 }
@@ -179,6 +303,9 @@ pub struct Client {
     // Basic info
     pub window_id: u64,
     pub window_name: String,
+    /// `WM_CLASS` of window
+    pub instance: Option<String>,
+    pub class: Option<String>,
     // Geometry
     pub x: i32,
     pub y: i32,
@@ -189,12 +316,20 @@ pub struct Client {
     pub ox: i32,
     pub oy: i32,
     pub border: u32,
+    /// Relative size in layout compared to other clients
+    pub factor: f64,
+    /// Name of scratchpad this client is
+    pub scratchpad: Option<String>,
     // Flags
     pub visible: bool,
     pub floating: bool,
     pub fullscreen: bool,
     pub fixed: bool,
     pub urgent: bool,
+    /// Shown on every workspace of its screen
+    pub sticky: bool,
+    /// Iconified, not shown and not tiled
+    pub minimized: bool,
     // Restrictions
     pub minw: i32,
     pub minh: i32,
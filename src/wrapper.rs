@@ -22,6 +22,131 @@ pub mod sys {
             let _ = sigaction(SIGCHLD, &sa);
         }
     }
+
+    /// Pipe written by SIGHUP handler. Waiting for events includes its read end, so signal
+    /// received right before waiting starts still wakes window manager
+    static RELOAD_READ_FD: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(-1);
+    static RELOAD_WRITE_FD: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(-1);
+
+    extern "C" fn reload_handler(_: i32) {
+        let fd = RELOAD_WRITE_FD.load(std::sync::atomic::Ordering::SeqCst);
+        unsafe {
+            // Handler must not change errno seen by interrupted code
+            let errno = *libc::__errno_location();
+            libc::write(fd, [1u8].as_ptr() as *const libc::c_void, 1);
+            *libc::__errno_location() = errno;
+        }
+    }
+
+    /// Request config reload on SIGHUP
+    pub fn catch_reload_signal() {
+        use nix::fcntl::OFlag;
+        use nix::sys::signal::*;
+        let (read_fd, write_fd) = match nix::unistd::pipe2(OFlag::O_NONBLOCK | OFlag::O_CLOEXEC) {
+            Ok(fds) => fds,
+            Err(e) => {
+                eprintln!("rtwm: failed to create pipe for SIGHUP: {}", e);
+                return;
+            }
+        };
+        RELOAD_READ_FD.store(read_fd, std::sync::atomic::Ordering::SeqCst);
+        RELOAD_WRITE_FD.store(write_fd, std::sync::atomic::Ordering::SeqCst);
+        unsafe {
+            let sa = SigAction::new(
+                SigHandler::Handler(reload_handler),
+                SaFlags::SA_RESTART,
+                SigSet::empty(),
+            );
+            let _ = sigaction(SIGHUP, &sa);
+        }
+    }
+
+    /// File descriptor which becomes readable when SIGHUP is received, -1 if there is none
+    pub fn reload_fd() -> i32 {
+        RELOAD_READ_FD.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Returns true once after SIGHUP was received
+    pub fn take_reload_request() -> bool {
+        let fd = reload_fd();
+        let mut requested = false;
+        let mut buffer = [0u8; 64];
+        while fd >= 0 && matches!(nix::unistd::read(fd, &mut buffer), Ok(n) if n > 0) {
+            requested = true;
+        }
+        requested
+    }
+
+    /// Block until one of `readable` file descriptors is readable, one of `writable` is
+    /// writable or signal is received
+    ///
+    /// Returns file descriptors which are ready
+    pub fn wait_ready(readable: &[i32], writable: &[i32]) -> Vec<i32> {
+        let mut pollfds: Vec<libc::pollfd> = readable
+            .iter()
+            .map(|fd| (*fd, libc::POLLIN))
+            .chain(writable.iter().map(|fd| (*fd, libc::POLLOUT)))
+            .map(|(fd, events)| libc::pollfd {
+                fd,
+                events,
+                revents: 0,
+            })
+            .collect();
+        unsafe {
+            libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, -1);
+        }
+        let mut ready: Vec<i32> = pollfds
+            .iter()
+            .filter(|p| p.revents != 0)
+            .map(|p| p.fd)
+            .collect();
+        ready.dedup();
+        ready
+    }
+
+    /// Make reading and writing of file descriptor return instead of waiting
+    pub fn set_nonblocking(fd: i32) -> bool {
+        use nix::fcntl::*;
+        fcntl(fd, FcntlArg::F_GETFL)
+            .and_then(|flags| {
+                let flags = OFlag::from_bits_truncate(flags) | OFlag::O_NONBLOCK;
+                fcntl(fd, FcntlArg::F_SETFL(flags))
+            })
+            .is_ok()
+    }
+
+    /// Write as much of buffer as nonblocking file descriptor accepts
+    ///
+    /// Returns amount of written bytes, `None` if file descriptor is broken
+    pub fn write_available(fd: i32, data: &[u8]) -> Option<usize> {
+        let mut written = 0;
+        while written < data.len() {
+            match nix::unistd::write(fd, &data[written..]) {
+                Ok(0) => return None,
+                Ok(n) => written += n,
+                Err(nix::errno::Errno::EINTR) => {}
+                Err(nix::errno::Errno::EAGAIN) => break,
+                Err(_) => return None,
+            }
+        }
+        Some(written)
+    }
+
+    /// Append everything nonblocking file descriptor has to `data`
+    ///
+    /// Returns true if end of file is reached or file descriptor is broken
+    pub fn read_available(fd: i32, data: &mut Vec<u8>) -> bool {
+        let mut buffer = [0u8; 4096];
+        loop {
+            match nix::unistd::read(fd, &mut buffer) {
+                Ok(0) => return true,
+                Ok(n) => data.extend_from_slice(&buffer[..n]),
+                Err(nix::errno::Errno::EINTR) => {}
+                Err(nix::errno::Errno::EAGAIN) => return false,
+                Err(_) => return true,
+            }
+        }
+    }
 }
 
 pub mod xlib {
@@ -97,6 +222,14 @@ pub mod xlib {
         }
     }
 
+    pub fn connection_number(display: &mut x11::xlib::Display) -> i32 {
+        unsafe { x11::xlib::XConnectionNumber(display as *mut x11::xlib::Display) }
+    }
+
+    pub fn pending(display: &mut x11::xlib::Display) -> i32 {
+        unsafe { x11::xlib::XPending(display as *mut x11::xlib::Display) }
+    }
+
     pub fn default_root_window(display: &mut x11::xlib::Display) -> u64 {
         unsafe { x11::xlib::XDefaultRootWindow(display as *mut x11::xlib::Display) }
     }
@@ -131,6 +264,12 @@ pub mod xlib {
         }
     }
 
+    pub fn ungrab_key(dpy: &mut x11::xlib::Display, keycode: i32, mask: u32) {
+        unsafe {
+            x11::xlib::XUngrabKey(dpy, keycode, mask, x11::xlib::XDefaultRootWindow(dpy));
+        }
+    }
+
     pub fn grab_button(dpy: &mut x11::xlib::Display, win: u64, button: u32, mask: u32) {
         unsafe {
             x11::xlib::XGrabButton(
@@ -663,6 +802,20 @@ pub mod xlib {
         }
     }
 
+    /// Drop crossing events caused by moving windows under pointer
+    pub fn drain_enter_events(display: &mut x11::xlib::Display) {
+        unsafe {
+            x11::xlib::XSync(display as *mut x11::xlib::Display, 0);
+            let mut ev: XEvent = XEvent { type_: 0 };
+            while x11::xlib::XCheckMaskEvent(
+                display as *mut x11::xlib::Display,
+                x11::xlib::EnterWindowMask,
+                &mut ev as *mut XEvent,
+            ) != 0
+            {}
+        }
+    }
+
     pub fn raise_window(display: &mut x11::xlib::Display, w: u64) {
         unsafe {
             x11::xlib::XRaiseWindow(display as *mut x11::xlib::Display, w);
@@ -699,6 +852,10 @@ pub mod xlib {
         }
     }
 
+    pub fn keysym_to_string(keysym: u32) -> Option<String> {
+        unsafe { crate::utils::cstr_to_string(x11::xlib::XKeysymToString(keysym as u64)) }
+    }
+
     pub fn change_property(
         display: &mut x11::xlib::Display,
         w: u64,